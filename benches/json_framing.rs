@@ -0,0 +1,76 @@
+//! Benchmarks for framing CLI output into JSON messages.
+//!
+//! Run with `cargo bench --bench json_framing`. Throughput should stay flat
+//! as message size grows, showing that framing cost is linear in input size.
+
+use claude_code_sdk::transport::subprocess_cli::SubprocessCLITransport;
+use claude_code_sdk::{ClaudeCodeOptions, SafetyLimits};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Build a pretty-printed message of roughly `size` bytes split over many lines
+fn multiline_message(size: usize) -> Vec<String> {
+    let item = format!(r#""{}","#, "x".repeat(1022));
+    let mut lines = vec![r#"{"type": "assistant", "items": ["#.to_string()];
+    lines.extend(std::iter::repeat_n(item, size / 1024));
+    lines.push(r#""end"]}"#.to_string());
+    lines
+}
+
+fn new_transport() -> SubprocessCLITransport {
+    SubprocessCLITransport::new("bench", ClaudeCodeOptions::default(), Some("nonexistent"))
+        .expect("Should create transport")
+        .with_safety_limits(SafetyLimits::generous())
+}
+
+fn bench_multiline_framing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("multiline_message");
+    group.sample_size(10);
+
+    for size_mb in [1usize, 2, 5, 10] {
+        let size = size_mb * 1024 * 1024;
+        let lines = multiline_message(size);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{}MB", size_mb)), &lines, |b, lines| {
+            b.iter(|| {
+                let mut transport = new_transport();
+                let mut parsed = 0;
+                for line in lines {
+                    if let Some(result) = transport.process_line(line.clone()) {
+                        result.expect("Should parse");
+                        parsed += 1;
+                    }
+                }
+                assert_eq!(parsed, 1);
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_raw_chunks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("raw_chunks");
+    group.sample_size(10);
+
+    let bytes = multiline_message(10 * 1024 * 1024).join("\n").into_bytes();
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.bench_function("10MB_64KB_reads", |b| {
+        b.iter(|| {
+            let mut transport = new_transport();
+            let mut parsed = 0;
+            for chunk in bytes.chunks(64 * 1024) {
+                transport.process_bytes(chunk);
+                while let Some(result) = transport.next_message() {
+                    result.expect("Should parse");
+                    parsed += 1;
+                }
+            }
+            assert_eq!(parsed, 1);
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_multiline_framing, bench_raw_chunks);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+//! Benchmarks comparing map-based and borrowed wire-model message parsing.
+//!
+//! Run with `cargo bench --bench message_parsing`. `wire_borrowed` measures
+//! decoding into the borrowed model alone, which is what ingestion pipelines
+//! that never materialize tool inputs pay; `wire` includes conversion into
+//! owned `Message`s, where tool inputs are still parsed into maps.
+
+use std::collections::HashMap;
+
+use claude_code_sdk::wire::WireMessage;
+use claude_code_sdk::{parse_message, parse_message_line};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn assistant_line(input_size: usize) -> String {
+    serde_json::json!({
+        "type": "assistant",
+        "message": {
+            "id": "msg_1",
+            "content": [
+                {"type": "text", "text": "I'll write the file now."},
+                {
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "Write",
+                    "input": {
+                        "file_path": "/tmp/out.rs",
+                        "content": "fn main() {}\n".repeat(input_size / 13),
+                    }
+                }
+            ]
+        },
+        "session_id": "session-1"
+    })
+    .to_string()
+}
+
+fn result_line() -> String {
+    serde_json::json!({
+        "type": "result",
+        "subtype": "success",
+        "duration_ms": 1200,
+        "duration_api_ms": 1000,
+        "is_error": false,
+        "num_turns": 3,
+        "session_id": "session-1",
+        "total_cost_usd": 0.0123,
+        "usage": {"input_tokens": 100, "output_tokens": 200},
+        "result": "Done"
+    })
+    .to_string()
+}
+
+fn bench_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_message");
+
+    let mut lines = vec![("result".to_string(), result_line())];
+    for size in [1024usize, 64 * 1024, 1024 * 1024] {
+        lines.push((format!("assistant_{}KB", size / 1024), assistant_line(size)));
+    }
+
+    for (name, line) in &lines {
+        group.throughput(Throughput::Bytes(line.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("map", name), line, |b, line| {
+            b.iter(|| {
+                let data: HashMap<String, serde_json::Value> =
+                    serde_json::from_str(line).expect("Should decode");
+                parse_message(data).expect("Should parse")
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("wire", name), line, |b, line| {
+            b.iter(|| {
+                parse_message_line(line)
+                    .expect("Should decode")
+                    .expect("Should parse")
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("wire_borrowed", name), line, |b, line| {
+            b.iter(|| WireMessage::from_line(line).expect("Should decode"));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parsing);
+criterion_main!(benches);
@@ -203,6 +203,7 @@ async fn with_tools_example() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 /// Demonstrate custom logging setup
+#[allow(dead_code)]
 fn setup_custom_logging() {
     println!("=== Custom Logging Setup Demo ===");
     
@@ -71,7 +71,7 @@ fn demonstrate_log_safety() {
     let long_text = "A".repeat(1000);
     let very_long_text = "B".repeat(10000);
     
-    println!("Short text preview: '{}'", limits.safe_log_preview(&short_text));
+    println!("Short text preview: '{}'", limits.safe_log_preview(short_text));
     println!("Long text preview: '{}'", limits.safe_log_preview(&long_text));
     println!("Very long text preview: '{}'", limits.safe_log_preview(&very_long_text));
     println!();
@@ -129,6 +129,7 @@ async fn demonstrate_safety_errors() -> Result<(), Box<dyn std::error::Error>> {
         max_buffered_messages: 5,     // Very small
         json_parse_timeout_ms: 100,   // Very short
         max_log_preview_chars: 20,    // Very short
+        max_json_lines: 10,           // Very few
     };
     
     // Test line size violation
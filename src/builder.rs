@@ -0,0 +1,389 @@
+//! Fluent builder for [`ClaudeCodeOptions`] with validation
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use tracing::debug;
+
+use crate::config_dir::ConfigDir;
+use crate::errors::ValidationError;
+use crate::sessions::CONFIG_DIR_ENV;
+use crate::ledger::Ledger;
+use crate::pricing::PricingTable;
+use crate::retry::RetryPolicy;
+use crate::mcp::{McpHttpServerConfig, McpServerConfig, McpSseServerConfig};
+use crate::types::{ClaudeCodeOptions, PermissionMode, SettingSource, Settings};
+
+/// Builds [`ClaudeCodeOptions`], checking for conflicting settings
+///
+/// ```rust
+/// use claude_code_sdk::{ClaudeCodeOptions, PermissionMode};
+///
+/// let options = ClaudeCodeOptions::builder()
+///     .model("sonnet")
+///     .allowed_tools(["Read", "Grep"])
+///     .permission_mode(PermissionMode::AcceptEdits)
+///     .max_turns(5)
+///     .build()
+///     .expect("valid options");
+/// assert_eq!(options.max_turns, Some(5));
+///
+/// let conflict = ClaudeCodeOptions::builder()
+///     .continue_conversation(true)
+///     .resume("3f1c")
+///     .build();
+/// assert!(conflict.is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ClaudeCodeOptionsBuilder {
+    options: ClaudeCodeOptions,
+}
+
+impl ClaudeCodeOptions {
+    /// Start building options from the defaults
+    pub fn builder() -> ClaudeCodeOptionsBuilder {
+        ClaudeCodeOptionsBuilder::default()
+    }
+
+    /// Check the options for settings that conflict or refer to things that do not exist
+    ///
+    /// Every problem found is reported, not just the first.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let mut problems = Vec::new();
+
+        if self.continue_conversation && self.resume.is_some() {
+            problems.push("continue_conversation and resume cannot both be set".to_string());
+        }
+        if self.fork_session && !self.continue_conversation && self.resume.is_none() {
+            problems.push("fork_session requires resume or continue_conversation".to_string());
+        }
+
+        let disallowed: HashSet<&str> = self.disallowed_tools.iter().map(String::as_str).collect();
+        let mut seen = HashSet::new();
+        let conflicting: Vec<&str> = self
+            .allowed_tools
+            .iter()
+            .map(String::as_str)
+            .filter(|tool| disallowed.contains(tool) && seen.insert(*tool))
+            .collect();
+        if !conflicting.is_empty() {
+            problems.push(format!("tools both allowed and disallowed: {}", conflicting.join(", ")));
+        }
+
+        if let Some(cwd) = &self.cwd {
+            if !cwd.is_dir() {
+                problems.push(format!("cwd {} is not an existing directory", cwd.display()));
+            }
+        }
+        if let Some(cli_path) = self.cli_path.as_ref().filter(|path| !path.is_file()) {
+            problems.push(format!("cli_path {} is not an existing file", cli_path.display()));
+        }
+        for dir in &self.add_dirs {
+            if !dir.is_dir() {
+                problems.push(format!("add_dirs entry {} is not an existing directory", dir.display()));
+            }
+        }
+        if let Some(Settings::File(path)) = &self.settings {
+            if !path.is_file() {
+                problems.push(format!("settings file {} does not exist", path.display()));
+            }
+        }
+        if let Some(Settings::Inline(value)) = &self.settings {
+            if !value.is_object() {
+                problems.push("inline settings must be a JSON object".to_string());
+            }
+        }
+        if let Some(config_dir) = &self.config_dir {
+            if let Some(path) = config_dir.path.as_ref().filter(|path| path.exists() && !path.is_dir()) {
+                problems.push(format!("config_dir {} is not a directory", path.display()));
+            }
+            if let Some(template) = config_dir.template.as_ref().filter(|template| !template.is_dir()) {
+                problems.push(format!("config_dir template {} is not an existing directory", template.display()));
+            }
+            if self.env.contains_key(CONFIG_DIR_ENV) {
+                problems.push(format!("config_dir and env {} cannot both be set", CONFIG_DIR_ENV));
+            }
+            if self.user.is_some() && config_dir.path.is_none() {
+                // The temporary directory belongs to this process's user, not the CLI's
+                problems.push("config_dir must have a path when user is set".to_string());
+            }
+        }
+        for name in self.env.keys() {
+            if name.is_empty() || name.contains(['=', '\0']) {
+                problems.push(format!("env variable name {:?} is not valid", name));
+            }
+        }
+        for flag in self.extra_args.keys() {
+            if flag.is_empty() || flag.starts_with('-') {
+                problems.push(format!("extra_args flag {:?} must be named without leading dashes", flag));
+            }
+        }
+        if self.max_turns == Some(0) {
+            problems.push("max_turns must be at least 1".to_string());
+        }
+        if let Some(budget) = self.max_budget_usd {
+            if !(budget.is_finite() && budget > 0.0) {
+                problems.push(format!("max_budget_usd must be a positive amount, got {}", budget));
+            }
+        }
+        if let Some(policy) = &self.retry_policy {
+            if policy.max_attempts == 0 {
+                problems.push("retry_policy.max_attempts must be at least 1".to_string());
+            }
+        }
+        let mut servers: Vec<_> = self.mcp_servers.iter().collect();
+        servers.sort_by_key(|(name, _)| *name);
+        for (name, server) in servers {
+            match server {
+                McpServerConfig::Stdio(config) if config.command.trim().is_empty() => {
+                    problems.push(format!("MCP server {} has no command", name));
+                }
+                McpServerConfig::Sse(McpSseServerConfig { url, .. })
+                | McpServerConfig::Http(McpHttpServerConfig { url, .. })
+                    if !(url.starts_with("http://") || url.starts_with("https://")) =>
+                {
+                    problems.push(format!("MCP server {} needs an http or https URL, got {:?}", name, url));
+                }
+                McpServerConfig::Sdk(_) => {
+                    problems.push(format!("MCP server {} is an SDK server, which this SDK cannot serve yet", name));
+                }
+                _ => {}
+            }
+        }
+
+        if problems.is_empty() {
+            debug!("Options are valid");
+            Ok(())
+        } else {
+            Err(ValidationError::new(problems))
+        }
+    }
+}
+
+impl ClaudeCodeOptionsBuilder {
+    /// Start from the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from existing options
+    pub fn from_options(options: ClaudeCodeOptions) -> Self {
+        Self { options }
+    }
+
+    /// Replace the tools Claude may use without asking
+    pub fn allowed_tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options.allowed_tools = tools.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Allow one more tool
+    pub fn allow_tool(mut self, tool: impl Into<String>) -> Self {
+        self.options.allowed_tools.push(tool.into());
+        self
+    }
+
+    /// Replace the tools Claude may not use
+    pub fn disallowed_tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options.disallowed_tools = tools.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Disallow one more tool
+    pub fn disallow_tool(mut self, tool: impl Into<String>) -> Self {
+        self.options.disallowed_tools.push(tool.into());
+        self
+    }
+
+    pub fn max_thinking_tokens(mut self, tokens: u32) -> Self {
+        self.options.max_thinking_tokens = Some(tokens);
+        self
+    }
+
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.options.system_prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn append_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.options.append_system_prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn mcp_tools<I, S>(mut self, tools: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options.mcp_tools = tools.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Add or replace an MCP server
+    pub fn mcp_server(mut self, name: impl Into<String>, config: McpServerConfig) -> Self {
+        self.options.mcp_servers.insert(name.into(), config);
+        self
+    }
+
+    /// Add or replace several MCP servers, such as those from [`McpServerConfig::load`]
+    pub fn mcp_servers<I, S>(mut self, servers: I) -> Self
+    where
+        I: IntoIterator<Item = (S, McpServerConfig)>,
+        S: Into<String>,
+    {
+        self.options
+            .mcp_servers
+            .extend(servers.into_iter().map(|(name, config)| (name.into(), config)));
+        self
+    }
+
+    pub fn permission_mode(mut self, mode: PermissionMode) -> Self {
+        self.options.permission_mode = Some(mode);
+        self
+    }
+
+    /// Continue the most recent conversation in the working directory
+    pub fn continue_conversation(mut self, continue_conversation: bool) -> Self {
+        self.options.continue_conversation = continue_conversation;
+        self
+    }
+
+    /// Resume a session by id
+    pub fn resume(mut self, session_id: impl Into<String>) -> Self {
+        self.options.resume = Some(session_id.into());
+        self
+    }
+
+    /// Resume into a new session id instead of appending to the resumed session
+    pub fn fork_session(mut self, fork_session: bool) -> Self {
+        self.options.fork_session = fork_session;
+        self
+    }
+
+    pub fn max_turns(mut self, max_turns: u32) -> Self {
+        self.options.max_turns = Some(max_turns);
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.options.model = Some(model.into());
+        self
+    }
+
+    pub fn permission_prompt_tool_name(mut self, tool: impl Into<String>) -> Self {
+        self.options.permission_prompt_tool_name = Some(tool.into());
+        self
+    }
+
+    pub fn cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.options.cwd = Some(cwd.into());
+        self
+    }
+
+    /// Run this Claude CLI executable instead of looking one up
+    pub fn cli_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.cli_path = Some(path.into());
+        self
+    }
+
+    /// Give Claude access to another directory besides `cwd`
+    pub fn add_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.options.add_dirs.push(dir.into());
+        self
+    }
+
+    pub fn settings(mut self, settings: Settings) -> Self {
+        self.options.settings = Some(settings);
+        self
+    }
+
+    pub fn setting_sources<I: IntoIterator<Item = SettingSource>>(mut self, sources: I) -> Self {
+        self.options.setting_sources = Some(sources.into_iter().collect());
+        self
+    }
+
+    pub fn strict_mcp_config(mut self, strict: bool) -> Self {
+        self.options.strict_mcp_config = strict;
+        self
+    }
+
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.options.user = Some(user.into());
+        self
+    }
+
+    /// Run the CLI with its own config directory
+    pub fn config_dir(mut self, config_dir: ConfigDir) -> Self {
+        self.options.config_dir = Some(config_dir);
+        self
+    }
+
+    /// Set an environment variable for the CLI
+    pub fn env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.env.insert(name.into(), Some(value.into()));
+        self
+    }
+
+    /// Keep an inherited environment variable from reaching the CLI
+    pub fn remove_env(mut self, name: impl Into<String>) -> Self {
+        self.options.env.insert(name.into(), None);
+        self
+    }
+
+    /// Start the CLI from an empty environment, inheriting only variables matching `allowlist`
+    pub fn env_clear<I, S>(mut self, allowlist: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options.env_clear = true;
+        self.options.env_allowlist = allowlist.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Pass a CLI flag the options do not cover, named without leading dashes
+    pub fn extra_arg(mut self, flag: impl Into<String>, value: Option<String>) -> Self {
+        self.options.extra_args.insert(flag.into(), value);
+        self
+    }
+
+    /// Add a model to fall back to after the ones already added
+    pub fn fallback_model(mut self, model: impl Into<String>) -> Self {
+        self.options.fallback_models.push(model.into());
+        self
+    }
+
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.options.retry_policy = Some(policy);
+        self
+    }
+
+    pub fn max_budget_usd(mut self, budget: f64) -> Self {
+        self.options.max_budget_usd = Some(budget);
+        self
+    }
+
+    pub fn pricing(mut self, pricing: PricingTable) -> Self {
+        self.options.pricing = Some(pricing);
+        self
+    }
+
+    pub fn ledger(mut self, ledger: Ledger) -> Self {
+        self.options.ledger = Some(ledger);
+        self
+    }
+
+    /// Validate and return the options
+    pub fn build(self) -> Result<ClaudeCodeOptions, ValidationError> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
+}
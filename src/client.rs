@@ -1,14 +1,19 @@
 //! Internal client implementation.
 
 use futures::Stream;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::pin::Pin;
+use std::time::Instant;
 use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 use tracing::{debug, error, info, warn, instrument, span, Level};
 
 use crate::{
+    config_dir::PreparedConfigDir,
     errors::ClaudeSDKError,
-    transport::{subprocess_cli::SubprocessCLITransport, Transport},
+    ledger::LedgerRecord,
+    pricing::{PricingTable, Usage},
+    transport::{subprocess_cli::{DecodedFrame, SubprocessCLITransport}, Transport},
+    retry::RetryPolicy,
     types::*,
     SafetyLimits,
 };
@@ -16,6 +21,146 @@ use crate::{
 /// Internal client implementation
 pub struct InternalClient;
 
+/// What happened during a single CLI invocation
+struct AttemptOutcome<T> {
+    /// Error result held back for a retry decision, with its converted item
+    held_error: Option<(ResultMessage, T)>,
+    /// Latest session id seen in the invocation's output
+    session_id: Option<String>,
+    /// Last result forwarded to the receiver
+    forwarded_result: Option<ResultMessage>,
+    receiver_dropped: bool,
+}
+
+/// Running cost of a query, checked against `max_budget_usd`
+///
+/// Each attempt is estimated from the usage on its assistant messages until
+/// its result reports the actual cost.
+struct BudgetGuard {
+    limit: Option<f64>,
+    pricing: PricingTable,
+    /// Cost of attempts that already produced a result
+    settled_usd: f64,
+    /// Estimated cost of the attempt in progress
+    attempt_usd: f64,
+    /// Token usage of attempts that already ended
+    settled_usage: Usage,
+    /// Whether any settled cost was estimated rather than reported by the CLI
+    settled_estimate: bool,
+    attempt_usage: Usage,
+    /// The CLI repeats an assistant message's usage for each content block
+    seen_message_ids: HashSet<String>,
+    /// Models without pricing whose usage has already been reported
+    warned_unpriced: HashSet<String>,
+}
+
+impl BudgetGuard {
+    fn new(options: &ClaudeCodeOptions) -> Self {
+        Self {
+            limit: options.max_budget_usd,
+            pricing: options.pricing.clone().unwrap_or_default(),
+            settled_usd: 0.0,
+            attempt_usd: 0.0,
+            settled_usage: Usage::default(),
+            settled_estimate: false,
+            attempt_usage: Usage::default(),
+            seen_message_ids: HashSet::new(),
+            warned_unpriced: HashSet::new(),
+        }
+    }
+
+    fn start_attempt(&mut self) {
+        // An attempt that ended without a result still cost what we estimated
+        self.settle_estimate();
+        self.seen_message_ids.clear();
+    }
+
+    /// Add the usage of a raw assistant message to the running estimate
+    fn record_assistant(&mut self, data: &HashMap<String, serde_json::Value>, current_model: Option<&str>) {
+        let Some(message) = data.get("message") else {
+            return;
+        };
+        if let Some(id) = message.get("id").and_then(|v| v.as_str()) {
+            if !self.seen_message_ids.insert(id.to_string()) {
+                return;
+            }
+        }
+        let Some(usage) = message.get("usage").and_then(Usage::from_value) else {
+            return;
+        };
+        self.attempt_usage += usage;
+
+        let model = message.get("model").and_then(|v| v.as_str()).or(current_model);
+        match model.and_then(|model| self.pricing.cost(model, &usage)) {
+            Some(cost) => self.attempt_usd += cost,
+            None => {
+                if self.warned_unpriced.insert(model.unwrap_or_default().to_string()) {
+                    // Without a price this usage never counts toward the budget or cost estimate
+                    warn!(model = ?model, limit_usd = ?self.limit, "No pricing for model, cost estimate leaves out its usage");
+                }
+            }
+        }
+        debug!(spent_usd = self.spent_usd(), limit_usd = ?self.limit, "Updated query cost estimate");
+    }
+
+    /// Cost of a result that did not report one, from its usage priced for
+    /// its own model or else `current_model`, or failing that the running
+    /// estimate
+    fn estimate_result_cost(&self, result: &ResultMessage, current_model: Option<&str>) -> Option<f64> {
+        let cost = result
+            .cost_usd(&self.pricing)
+            .or_else(|| self.pricing.cost(current_model?, &result.typed_usage()?))
+            .or((self.attempt_usd > 0.0).then_some(self.attempt_usd));
+        debug!(cost_usd = ?cost, "Estimated cost for result without total_cost_usd");
+        cost
+    }
+
+    /// Replace the attempt's estimate with the cost and usage its result reports
+    fn settle(&mut self, result: &ResultMessage) {
+        self.settled_estimate |= result.total_cost_usd.is_none() && (result.estimated_cost_usd.is_some() || self.attempt_usd > 0.0);
+        self.settled_usd += result
+            .total_cost_usd
+            .or(result.estimated_cost_usd)
+            .unwrap_or(self.attempt_usd);
+        self.settled_usage += result.typed_usage().unwrap_or(self.attempt_usage);
+        self.attempt_usd = 0.0;
+        self.attempt_usage = Usage::default();
+    }
+
+    /// Keep the attempt's estimate when it ends without a reported cost
+    fn settle_estimate(&mut self) {
+        self.settled_estimate |= self.attempt_usd > 0.0;
+        self.settled_usd += self.attempt_usd;
+        self.settled_usage += self.attempt_usage;
+        self.attempt_usd = 0.0;
+        self.attempt_usage = Usage::default();
+    }
+
+    fn spent_usd(&self) -> f64 {
+        self.settled_usd + self.attempt_usd
+    }
+
+    /// Whether `spent_usd` includes anything the CLI did not report itself
+    fn spent_is_estimate(&self) -> bool {
+        self.settled_estimate || self.attempt_usd > 0.0
+    }
+
+    fn spent_usage(&self) -> Usage {
+        let mut usage = self.settled_usage;
+        usage += self.attempt_usage;
+        usage
+    }
+
+    fn exceeded(&self) -> bool {
+        self.limit.is_some_and(|limit| self.spent_usd() > limit)
+    }
+
+    /// Whether another attempt would start with no budget left
+    fn exhausted(&self) -> bool {
+        self.limit.is_some_and(|limit| self.spent_usd() >= limit)
+    }
+}
+
 impl InternalClient {
     /// Create a new internal client
     pub fn new() -> Self {
@@ -31,9 +176,90 @@ impl InternalClient {
     ) -> Result<Pin<Box<dyn Stream<Item = Message> + Send>>, ClaudeSDKError> {
         info!("Processing query through transport");
         
-        let transport = SubprocessCLITransport::new(prompt, options, None)?;
+        let (options, config_dir) = Self::prepare_config_dir(options)?;
+        let transport = SubprocessCLITransport::new(prompt, options.clone(), None)?;
         debug!("Created subprocess CLI transport");
 
+        let stream = Self::spawn_transport_task(
+            transport,
+            prompt.to_string(),
+            options,
+            config_dir,
+            |_, message| message,
+            |message| message,
+        );
+        Ok(Box::pin(stream))
+    }
+
+    /// Process a query through transport, keeping each message's raw JSON
+    #[instrument(level = "debug", skip(self, prompt, options))]
+    pub async fn process_query_raw(
+        &self,
+        prompt: &str,
+        options: ClaudeCodeOptions,
+    ) -> Result<Pin<Box<dyn Stream<Item = RawMessage> + Send>>, ClaudeSDKError> {
+        info!("Processing raw query through transport");
+        
+        let (options, config_dir) = Self::prepare_config_dir(options)?;
+        let transport = SubprocessCLITransport::new(prompt, options.clone(), None)?;
+        debug!("Created subprocess CLI transport");
+
+        let stream = Self::spawn_transport_task(
+            transport,
+            prompt.to_string(),
+            options,
+            config_dir,
+            |frame, message| {
+                // Keep the CLI's own text; the decoded map has lost its key order.
+                // The frame already decoded, so neither conversion can fail.
+                let raw = String::from_utf8(frame.bytes)
+                    .ok()
+                    .and_then(|json| serde_json::value::RawValue::from_string(json).ok())
+                    .unwrap_or_else(|| serde_json::value::RawValue::NULL.to_owned());
+                Some(RawMessage { raw, message })
+            },
+            RawMessage::from_message,
+        );
+        Ok(Box::pin(stream))
+    }
+
+    /// Create the query's config directory, if it has one, and point the CLI at it
+    ///
+    /// The returned guard removes the directory when dropped, so it is kept
+    /// alive until every attempt of the query has finished.
+    fn prepare_config_dir(
+        mut options: ClaudeCodeOptions,
+    ) -> Result<(ClaudeCodeOptions, Option<PreparedConfigDir>), ClaudeSDKError> {
+        let Some(config_dir) = &options.config_dir else {
+            return Ok((options, None));
+        };
+        let prepared = config_dir.prepare()?;
+        let (name, value) = prepared.env();
+        options.env.insert(name, Some(value));
+        Ok((options, Some(prepared)))
+    }
+
+    /// Drive the transport on a background task, turning each decoded message
+    /// and its typed form into a stream item with `convert`, and reporting
+    /// failures through `from_error`
+    ///
+    /// Runs ending in an overload or model error switch to the next model in
+    /// `options.fallback_models`. When `options.retry_policy` is set, runs
+    /// ending in a retryable error are re-launched. Either way the session is
+    /// resumed if the failed run created one.
+    fn spawn_transport_task<T, C, E>(
+        transport: SubprocessCLITransport,
+        prompt: String,
+        options: ClaudeCodeOptions,
+        config_dir: Option<PreparedConfigDir>,
+        convert: C,
+        from_error: E,
+    ) -> ReceiverStream<T>
+    where
+        T: Send + 'static,
+        C: Fn(DecodedFrame, Option<Message>) -> Option<T> + Send + Sync + 'static,
+        E: Fn(Message) -> T + Send + Sync + 'static,
+    {
         let safety_limits = SafetyLimits::default();
         let channel_size = safety_limits.max_buffered_messages.min(1000); // Cap at 1000 for safety
         let (tx, rx) = tokio::sync::mpsc::channel(channel_size);
@@ -43,85 +269,371 @@ impl InternalClient {
         tokio::spawn(async move {
             let span = span!(Level::DEBUG, "transport_task");
             let _enter = span.enter();
-            
+
+            let policy = options.retry_policy.clone().unwrap_or_else(RetryPolicy::none);
+            let mut budget = BudgetGuard::new(&options);
+            let mut options = options;
             let mut transport = transport;
-            debug!("Starting transport connection");
-            
-            if let Err(e) = transport.connect().await {
-                error!(error = %e, "Failed to connect to transport");
-                let _ = tx.send(Message::Result(ResultMessage {
-                    subtype: "error".to_string(),
-                    duration_ms: 0,
-                    duration_api_ms: 0,
-                    is_error: true,
-                    num_turns: 0,
-                    session_id: "error".to_string(),
-                    total_cost_usd: None,
-                    usage: None,
-                    result: Some(format!("Connection error: {}", e)),
-                })).await;
-                return;
+            let mut attempt = 1u32;
+            // Time and turns of attempts that were run again
+            let mut earlier_duration_ms = 0u64;
+            let mut earlier_turns = 0u32;
+
+            let final_result = loop {
+                let can_retry = attempt < policy.max_attempts;
+                let hold_errors = can_retry || !options.fallback_models.is_empty();
+                let outcome = Self::run_attempt(
+                    transport,
+                    options.model.clone(),
+                    &mut budget,
+                    &tx,
+                    &convert,
+                    &from_error,
+                    hold_errors,
+                )
+                .await;
+                if outcome.receiver_dropped {
+                    break outcome.forwarded_result;
+                }
+
+                // Only error results are held back; anything else means we're done
+                let Some((result, item)) = outcome.held_error else {
+                    break outcome.forwarded_result;
+                };
+                let Some(error) = result.clone().into_result().err() else {
+                    let _ = tx.send(item).await;
+                    break Some(result);
+                };
+                if budget.exhausted() {
+                    warn!(spent_usd = budget.spent_usd(), "Budget exhausted, not running again");
+                    let _ = tx.send(item).await;
+                    break Some(result);
+                }
+
+                let use_fallback = !options.fallback_models.is_empty()
+                    && matches!(error, ClaudeSDKError::Overloaded(_) | ClaudeSDKError::InvalidModel(_));
+                if use_fallback {
+                    let fallback = options.fallback_models.remove(0);
+                    warn!(
+                        from_model = ?options.model,
+                        to_model = %fallback,
+                        error = %error,
+                        "Switching to fallback model"
+                    );
+                    options.model = Some(fallback);
+                } else if can_retry && policy.is_retryable(&error) {
+                    let retry_after = error.api_error().and_then(|e| e.retry_after);
+                    let delay = policy.backoff(attempt, retry_after);
+                    warn!(
+                        attempt,
+                        max_attempts = policy.max_attempts,
+                        delay_ms = delay.as_millis() as u64,
+                        error = %error,
+                        "Retrying query after retryable error"
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                } else {
+                    let _ = tx.send(item).await;
+                    break Some(result);
+                }
+                earlier_duration_ms += result.duration_ms;
+                earlier_turns += result.num_turns;
+
+                let session_id = Some(result.session_id.clone())
+                    .filter(|id| id != "error")
+                    .or(outcome.session_id);
+                let next_prompt = match session_id {
+                    Some(session_id) => {
+                        debug!(session_id = %session_id, "Resuming session for next attempt");
+                        options.resume = Some(session_id);
+                        options.continue_conversation = false;
+                        // The failed attempt already branched off if it was forking
+                        options.fork_session = false;
+                        policy.resume_prompt.as_str()
+                    }
+                    None => prompt.as_str(),
+                };
+                transport = match SubprocessCLITransport::new(next_prompt, options.clone(), None) {
+                    Ok(transport) => transport,
+                    Err(e) => {
+                        error!(error = %e, "Failed to create transport for next attempt");
+                        let _ = tx.send(item).await;
+                        break Some(result);
+                    }
+                };
+            };
+
+            if let (Some(ledger), Some(result)) = (options.ledger.clone(), final_result) {
+                let cwd = options.cwd.clone().or_else(|| std::env::current_dir().ok());
+                let mut record = LedgerRecord::from_result(&result, cwd.as_deref());
+                // Include what failed attempts cost, used and took before the final one
+                record.usage = budget.spent_usage();
+                if record.cost_usd.is_some() || budget.spent_usd() > 0.0 {
+                    record.cost_usd = Some(budget.spent_usd());
+                    record.cost_is_estimate = budget.spent_is_estimate();
+                }
+                record.duration_ms += earlier_duration_ms;
+                record.num_turns += earlier_turns;
+                // Ledger writes are blocking file I/O
+                let path = ledger.path().to_path_buf();
+                match tokio::task::spawn_blocking(move || ledger.append(&record)).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => warn!(error = %e, path = %path.display(), "Failed to record query in ledger"),
+                    Err(e) => warn!(error = %e, path = %path.display(), "Ledger append task failed"),
+                }
             }
-            
-            info!("Successfully connected to transport");
 
-            {
-                let mut message_stream = transport.receive_messages();
-                let mut message_count = 0u64;
-                
-                debug!("Starting message stream processing");
-                while let Some(data_result) = message_stream.next().await {
-                    match data_result {
-                        Ok(data) => {
-                            debug!(message_count, "Received raw message data");
-                            if let Some(message) = Self::parse_message(data) {
-                                message_count += 1;
-                                debug!(message_count, message_type = ?std::mem::discriminant(&message), "Parsed message");
-                                
-                                if tx.send(message).await.is_err() {
-                                    warn!("Receiver dropped, stopping message processing");
-                                    break; // Receiver dropped
-                                }
-                            } else {
-                                warn!("Failed to parse message data");
+            // Remove a temporary config dir before the stream ends, so callers never see it linger
+            if let Some(config_dir) = config_dir {
+                debug!(path = %config_dir.path().display(), "Query finished with its config directory");
+                drop(config_dir);
+            }
+            drop(tx);
+        });
+
+        stream
+    }
+
+    /// Run one CLI invocation, forwarding its messages to `tx`
+    ///
+    /// Result messages without a model are annotated with the one the CLI
+    /// reported on its last assistant message, if any. Costs are estimated
+    /// for the model in use, taken from the CLI's init message or else
+    /// `requested_model`. With `hold_errors`, an error result is returned
+    /// instead of being sent, so the caller can decide whether to run again.
+    async fn run_attempt<T, C, E>(
+        mut transport: SubprocessCLITransport,
+        requested_model: Option<String>,
+        budget: &mut BudgetGuard,
+        tx: &tokio::sync::mpsc::Sender<T>,
+        convert: &C,
+        from_error: &E,
+        hold_errors: bool,
+    ) -> AttemptOutcome<T>
+    where
+        C: Fn(DecodedFrame, Option<Message>) -> Option<T>,
+        E: Fn(Message) -> T,
+    {
+        let mut outcome = AttemptOutcome {
+            held_error: None,
+            session_id: None,
+            forwarded_result: None,
+            receiver_dropped: false,
+        };
+        let mut model = requested_model;
+        let mut answered_by = None;
+        // A turn is a user message (the prompt or tool results) and the assistant's reply
+        let mut turns = 0u32;
+        let mut answering = false;
+        let started = Instant::now();
+        budget.start_attempt();
+        let error_result = |result: String| ResultMessage {
+            subtype: SDK_ERROR_SUBTYPE.to_string(),
+            duration_ms: 0,
+            duration_api_ms: 0,
+            is_error: true,
+            num_turns: 0,
+            session_id: "error".to_string(),
+            total_cost_usd: None,
+            usage: None,
+            result: Some(result),
+            model: None,
+            estimated_cost_usd: None,
+        };
+
+        debug!("Starting transport connection");
+        if let Err(e) = transport.connect().await {
+            error!(error = %e, "Failed to connect to transport");
+            let result = error_result(format!("Connection error: {}", e));
+            Self::report_error(&mut outcome, result, hold_errors, tx, from_error).await;
+            return outcome;
+        }
+        
+        info!("Successfully connected to transport");
+
+        let mut message_stream = transport.receive_decoded();
+        let mut message_count = 0u64;
+        let mut saw_result = false;
+        
+        debug!("Starting message stream processing");
+        while let Some(data_result) = message_stream.next().await {
+            match data_result {
+                Ok(frame) => {
+                    let data = &frame.data;
+                    debug!(message_count, "Received raw message data");
+                    if let Some(session_id) = data.get("session_id").and_then(|v| v.as_str()) {
+                        outcome.session_id = Some(session_id.to_string());
+                    }
+                    match data.get("type").and_then(|v| v.as_str()) {
+                        Some("assistant") if !answering => {
+                            answering = true;
+                            turns += 1;
+                        }
+                        Some("user") => answering = false,
+                        _ => {}
+                    }
+
+                    let mut message = Self::parse_message(data);
+                    let mut held_result = None;
+                    match &mut message {
+                        Some(Message::System(system)) if system.subtype == "init" => {
+                            if let Some(init_model) = system.data.get("model").and_then(|v| v.as_str()) {
+                                model = Some(init_model.to_string());
+                            }
+                        }
+                        Some(Message::Assistant(_)) => {
+                            if let Some(assistant_model) = data
+                                .get("message")
+                                .and_then(|m| m.get("model"))
+                                .and_then(|v| v.as_str())
+                            {
+                                answered_by = Some(assistant_model.to_string());
+                            }
+                            budget.record_assistant(data, model.as_deref());
+                        }
+                        Some(Message::Result(result)) => {
+                            saw_result = true;
+                            if result.model.is_none() {
+                                result.model = answered_by.clone();
+                            }
+                            if result.total_cost_usd.is_none() {
+                                result.estimated_cost_usd = budget.estimate_result_cost(result, model.as_deref());
                             }
+                            budget.settle(result);
+                            if hold_errors && (result.is_error || result.subtype != "success") {
+                                held_result = Some(result.clone());
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    let forwarded = match &message {
+                        Some(Message::Result(result)) => Some(result.clone()),
+                        _ => None,
+                    };
+                    if let Some(item) = convert(frame, message) {
+                        message_count += 1;
+                        debug!(message_count, "Parsed message");
+
+                        if let Some(result) = held_result {
+                            debug!("Holding back error result for retry decision");
+                            outcome.held_error = Some((result, item));
+                            continue;
                         }
-                        Err(e) => {
-                            error!(error = %e, "Error receiving message from transport");
-                            let error_message = Message::Result(ResultMessage {
-                                subtype: "error".to_string(),
-                                duration_ms: 0,
+                        if forwarded.is_some() {
+                            outcome.forwarded_result = forwarded;
+                        }
+                        
+                        if tx.send(item).await.is_err() {
+                            warn!("Receiver dropped, stopping message processing");
+                            outcome.receiver_dropped = true;
+                            break; // Receiver dropped
+                        }
+
+                        if !saw_result && budget.exceeded() {
+                            let spent = budget.spent_usd();
+                            warn!(spent_usd = spent, limit_usd = ?budget.limit, "Budget exceeded, interrupting query");
+                            let result = ResultMessage {
+                                subtype: "error_max_budget_usd".to_string(),
+                                duration_ms: started.elapsed().as_millis() as u64,
                                 duration_api_ms: 0,
                                 is_error: true,
-                                num_turns: 0,
-                                session_id: "error".to_string(),
+                                num_turns: turns,
+                                session_id: outcome.session_id.clone().unwrap_or_else(|| "error".to_string()),
                                 total_cost_usd: None,
-                                usage: None,
-                                result: Some(format!("Stream error: {}", e)),
-                            });
-                            let _ = tx.send(error_message).await;
+                                usage: Some(budget.attempt_usage.to_map()),
+                                result: Some(format!(
+                                    "Budget of ${:.2} exceeded: spent ${:.4}",
+                                    budget.limit.unwrap_or_default(),
+                                    spent
+                                )),
+                                model: answered_by.clone(),
+                                estimated_cost_usd: Some(spent),
+                            };
+                            budget.settle_estimate();
+                            Self::report_error(&mut outcome, result, hold_errors, tx, from_error).await;
+                            saw_result = true;
                             break;
                         }
+                    } else {
+                        warn!("Failed to parse message data");
                     }
                 }
-                
-                info!(message_count, "Finished processing message stream");
-            } // Drop message_stream here
-
-            debug!("Disconnecting from transport");
-            if let Err(e) = transport.disconnect().await {
-                warn!(error = %e, "Error during transport disconnect");
-            } else {
-                debug!("Successfully disconnected from transport");
+                Err(ClaudeSDKError::CLIJSONDecode(e)) => {
+                    // The transport has already resynchronized past the broken fragment,
+                    // so report it and keep reading
+                    warn!(error = %e, "Malformed message from transport");
+                    let mut data = HashMap::new();
+                    data.insert("type".to_string(), serde_json::Value::from("system"));
+                    data.insert("subtype".to_string(), serde_json::Value::from(JSON_DECODE_ERROR_SUBTYPE));
+                    data.insert("error".to_string(), serde_json::Value::from(e.original_error.to_string()));
+                    data.insert("line".to_string(), serde_json::Value::from(e.line.clone()));
+                    let item = from_error(Message::System(SystemMessage {
+                        subtype: JSON_DECODE_ERROR_SUBTYPE.to_string(),
+                        data,
+                    }));
+                    message_count += 1;
+                    if tx.send(item).await.is_err() {
+                        warn!("Receiver dropped, stopping message processing");
+                        outcome.receiver_dropped = true;
+                        break;
+                    }
+                }
+                Err(e) => {
+                    error!(error = %e, "Error receiving message from transport");
+                    let result = error_result(format!("Stream error: {}", e));
+                    Self::report_error(&mut outcome, result, hold_errors, tx, from_error).await;
+                    saw_result = true;
+                    break;
+                }
             }
-        });
+        }
+        
+        info!(message_count, "Finished processing message stream");
+        drop(message_stream);
 
-        Ok(Box::pin(stream))
+        // A run that ends without a result usually explains why on stderr
+        if !saw_result && !tx.is_closed() {
+            let stderr = transport.captured_stderr().await;
+            if !stderr.trim().is_empty() {
+                warn!(stderr_length = stderr.len(), "CLI exited without a result message");
+                let result = error_result(stderr.trim().to_string());
+                Self::report_error(&mut outcome, result, hold_errors, tx, from_error).await;
+            }
+        }
+
+        debug!("Disconnecting from transport");
+        if let Err(e) = transport.disconnect().await {
+            warn!(error = %e, "Error during transport disconnect");
+        } else {
+            debug!("Successfully disconnected from transport");
+        }
+
+        outcome
+    }
+
+    /// Hold or forward a synthesized error result
+    async fn report_error<T, E>(
+        outcome: &mut AttemptOutcome<T>,
+        result: ResultMessage,
+        hold: bool,
+        tx: &tokio::sync::mpsc::Sender<T>,
+        from_error: &E,
+    ) where
+        E: Fn(Message) -> T,
+    {
+        if hold {
+            outcome.held_error = Some((result.clone(), from_error(Message::Result(result))));
+        } else {
+            outcome.forwarded_result = Some(result.clone());
+            let _ = tx.send(from_error(Message::Result(result))).await;
+        }
     }
 
     /// Parse message from CLI output, trusting the structure
     #[instrument(level = "trace", skip(data))]
-    fn parse_message(data: HashMap<String, serde_json::Value>) -> Option<Message> {
+    pub(crate) fn parse_message(data: &HashMap<String, serde_json::Value>) -> Option<Message> {
         let message_type = data.get("type")?.as_str()?;
         debug!(message_type, "Parsing message");
 
@@ -195,7 +707,7 @@ impl InternalClient {
             }
             "system" => {
                 let subtype = data.get("subtype")?.as_str()?.to_string();
-                let data_map: HashMap<String, serde_json::Value> = data.into_iter().collect();
+                let data_map = data.clone();
                 
                 debug!(subtype = %subtype, "Parsed system message");
                 Some(Message::System(SystemMessage {
@@ -215,6 +727,7 @@ impl InternalClient {
                     v.as_object().map(|o| o.clone().into_iter().collect())
                 });
                 let result = data.get("result").and_then(|v| v.as_str().map(|s| s.to_string()));
+                let model = data.get("model").and_then(|v| v.as_str().map(|s| s.to_string()));
                 
                 debug!(
                     subtype = %subtype,
@@ -236,6 +749,8 @@ impl InternalClient {
                     total_cost_usd,
                     usage,
                     result,
+                    model,
+                    estimated_cost_usd: None,
                 }))
             }
             _ => {
@@ -20,6 +20,10 @@ pub struct SafetyLimits {
     
     /// Timeout for JSON parsing operations (milliseconds)
     pub json_parse_timeout_ms: u64,
+    
+    /// Maximum number of lines a single JSON message may span before it is
+    /// treated as malformed and parsing resynchronizes
+    pub max_json_lines: usize,
 }
 
 impl Default for SafetyLimits {
@@ -42,6 +46,9 @@ impl Default for SafetyLimits {
             
             // 5 second timeout for JSON parsing
             json_parse_timeout_ms: 5000,
+            
+            // 100k lines per message - far beyond any pretty-printed response
+            max_json_lines: 100_000,
         }
     }
 }
@@ -61,6 +68,7 @@ impl SafetyLimits {
             max_log_preview_chars: 100,
             max_buffered_messages: 50,
             json_parse_timeout_ms: 2000,
+            max_json_lines: 10_000,
         }
     }
     
@@ -73,9 +81,16 @@ impl SafetyLimits {
             max_log_preview_chars: 500,
             max_buffered_messages: 200,
             json_parse_timeout_ms: 10000,
+            max_json_lines: 1_000_000,
         }
     }
-    
+
+    /// Set how many lines a single JSON message may span
+    pub fn with_max_json_lines(mut self, max_json_lines: usize) -> Self {
+        self.max_json_lines = max_json_lines;
+        self
+    }
+
     /// Check if a line size is within limits
     pub fn is_line_size_safe(&self, size: usize) -> bool {
         size <= self.max_line_size
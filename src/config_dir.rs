@@ -0,0 +1,213 @@
+//! Per-query Claude config directories
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::errors::{ClaudeSDKError, ConfigError};
+use crate::sessions::CONFIG_DIR_ENV;
+
+/// Distinguishes temporary directories created in the same nanosecond
+static TEMP_DIR_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Claude config directory (settings, sessions, credentials) for a query
+///
+/// Points the CLI at its own directory instead of `~/.claude`, so agents
+/// on one host do not share state. A temporary directory lives for a
+/// single query, including its retries, so [`Conversation`](crate::Conversation)s
+/// that resume across queries need a fixed [`ConfigDir::at`] path.
+///
+/// ```rust
+/// use claude_code_sdk::{ClaudeCodeOptions, ConfigDir};
+///
+/// let options = ClaudeCodeOptions {
+///     config_dir: Some(ConfigDir::temporary().with_template("/etc/claude-agents/template")),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigDir {
+    /// Directory to use; a new temporary directory when `None`
+    pub path: Option<PathBuf>,
+
+    /// Directory whose contents are copied in before the run
+    ///
+    /// Files already present are kept, so a fixed directory is only
+    /// seeded once.
+    pub template: Option<PathBuf>,
+
+    /// Delete the directory once the query finishes
+    ///
+    /// Defaults to deleting temporary directories and keeping fixed ones.
+    pub cleanup: Option<bool>,
+}
+
+impl ConfigDir {
+    /// A new temporary directory, deleted after the query
+    pub fn temporary() -> Self {
+        Self::default()
+    }
+
+    /// A fixed directory, created if missing and kept after the query
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: Some(path.into()),
+            ..Self::default()
+        }
+    }
+
+    /// Seed the directory from `template`
+    pub fn with_template(mut self, template: impl Into<PathBuf>) -> Self {
+        self.template = Some(template.into());
+        self
+    }
+
+    /// Keep the directory after the query
+    pub fn keep(mut self) -> Self {
+        self.cleanup = Some(false);
+        self
+    }
+
+    /// Delete the directory after the query
+    pub fn cleanup(mut self) -> Self {
+        self.cleanup = Some(true);
+        self
+    }
+
+    /// Create and seed the directory
+    pub(crate) fn prepare(&self) -> Result<PreparedConfigDir, ClaudeSDKError> {
+        let path = match &self.path {
+            Some(path) => {
+                std::fs::create_dir_all(path).map_err(|e| {
+                    ConfigError::with_path(format!("Failed to create config directory: {}", e), path)
+                })?;
+                path.clone()
+            }
+            None => create_temp_dir()?,
+        };
+
+        if let Some(template) = &self.template {
+            let copied = copy_missing(template, &path).map_err(|e| {
+                ConfigError::with_path(format!("Failed to copy config template: {}", e), template)
+            })?;
+            debug!(template = %template.display(), files = copied, "Seeded config directory");
+        }
+
+        let cleanup = self.cleanup.unwrap_or(self.path.is_none());
+        info!(path = %path.display(), cleanup, "Prepared config directory");
+        Ok(PreparedConfigDir { path, cleanup })
+    }
+}
+
+/// A config directory in use by a query, deleted on drop if requested
+#[derive(Debug)]
+pub(crate) struct PreparedConfigDir {
+    path: PathBuf,
+    cleanup: bool,
+}
+
+impl PreparedConfigDir {
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Environment variable pointing the CLI at this directory
+    pub(crate) fn env(&self) -> (String, String) {
+        (CONFIG_DIR_ENV.to_string(), self.path.to_string_lossy().into_owned())
+    }
+}
+
+impl Drop for PreparedConfigDir {
+    fn drop(&mut self) {
+        if !self.cleanup {
+            return;
+        }
+        match std::fs::remove_dir_all(&self.path) {
+            Ok(()) => debug!(path = %self.path.display(), "Removed config directory"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!(path = %self.path.display(), error = %e, "Failed to remove config directory"),
+        }
+    }
+}
+
+/// Create a private directory under the system temp dir
+fn create_temp_dir() -> Result<PathBuf, ClaudeSDKError> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let counter = TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!(
+        "claude-sdk-config-{}-{}-{}",
+        std::process::id(),
+        nanos,
+        counter
+    ));
+
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        // Credentials may end up in here
+        builder.mode(0o700);
+    }
+    builder
+        .create(&path)
+        .map_err(|e| ConfigError::with_path(format!("Failed to create temporary config directory: {}", e), &path))?;
+    Ok(path)
+}
+
+/// Copy files from `from` into `to` that `to` does not have yet, returning how many were copied
+///
+/// Symlinks are resolved and their contents copied, but only when they point
+/// inside the template; links that leave it, dangle or loop are skipped, so
+/// a template cannot pull in files from outside it.
+fn copy_missing(from: &Path, to: &Path) -> std::io::Result<usize> {
+    let root = std::fs::canonicalize(from)?;
+    copy_tree(&root, to, &root, &mut vec![root.clone()])
+}
+
+/// Copy `from`, a canonical directory under `root`, skipping links back into `visiting`
+fn copy_tree(from: &Path, to: &Path, root: &Path, visiting: &mut Vec<PathBuf>) -> std::io::Result<usize> {
+    let mut copied = 0;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let mut source = entry.path();
+        let target = to.join(entry.file_name());
+        let mut file_type = std::fs::symlink_metadata(&source)?.file_type();
+        if file_type.is_symlink() {
+            let resolved = match std::fs::canonicalize(&source) {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    warn!(link = %source.display(), error = %e, "Skipping unresolvable symlink in config template");
+                    continue;
+                }
+            };
+            if !resolved.starts_with(root) {
+                warn!(link = %source.display(), "Skipping symlink that points outside the config template");
+                continue;
+            }
+            if visiting.contains(&resolved) {
+                warn!(link = %source.display(), "Skipping symlink loop in config template");
+                continue;
+            }
+            file_type = std::fs::metadata(&resolved)?.file_type();
+            source = resolved;
+        }
+
+        if file_type.is_dir() {
+            std::fs::create_dir_all(&target)?;
+            visiting.push(source.clone());
+            copied += copy_tree(&source, &target, root, visiting)?;
+            visiting.pop();
+        } else if !target.exists() {
+            std::fs::copy(&source, &target)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
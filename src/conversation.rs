@@ -0,0 +1,145 @@
+//! Multi-turn conversations that carry the session id between queries
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use futures::{Stream, StreamExt};
+use tracing::{debug, info, instrument};
+
+use crate::client::InternalClient;
+use crate::errors::ClaudeSDKError;
+use crate::types::*;
+
+/// Session state shared between a conversation and its in-flight streams
+#[derive(Debug, Default)]
+struct SessionState {
+    session_id: Option<String>,
+    /// Queries should branch off into a new session until one reports its id
+    fork_pending: bool,
+}
+
+/// A conversation with Claude Code spanning several queries
+///
+/// Each [`Conversation::ask`] resumes the session the previous one ended in,
+/// so follow-up prompts see the whole history. The session id is picked up
+/// from the stream as it is consumed. Clones share the same session; use
+/// [`Conversation::fork`] to branch off.
+///
+/// ```rust,no_run
+/// use claude_code_sdk::{ClaudeCodeOptions, Conversation};
+/// use tokio_stream::StreamExt;
+///
+/// # async fn example() -> Result<(), claude_code_sdk::ClaudeSDKError> {
+/// let conversation = Conversation::new(ClaudeCodeOptions::default());
+/// let _ = conversation.ask("Read src/lib.rs").await?.collect::<Vec<_>>().await;
+/// let _ = conversation.ask("Now summarize it").await?.collect::<Vec<_>>().await;
+///
+/// // Explore an alternative without touching the original session
+/// let branch = conversation.fork();
+/// let _ = branch.ask("Rewrite it in a functional style").await?.collect::<Vec<_>>().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    options: ClaudeCodeOptions,
+    state: Arc<Mutex<SessionState>>,
+}
+
+impl Conversation {
+    /// Start a conversation that applies `options` to every query
+    ///
+    /// If `options.resume` is set, the conversation continues that session.
+    pub fn new(options: ClaudeCodeOptions) -> Self {
+        let mut options = options;
+        let state = SessionState {
+            session_id: options.resume.take(),
+            fork_pending: std::mem::take(&mut options.fork_session),
+        };
+        Self {
+            options,
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Continue an existing session
+    pub fn resume(session_id: impl Into<String>, options: ClaudeCodeOptions) -> Self {
+        Self::new(ClaudeCodeOptions {
+            resume: Some(session_id.into()),
+            ..options
+        })
+    }
+
+    /// Session the next query will resume, if any
+    pub fn session_id(&self) -> Option<String> {
+        self.lock_state().session_id.clone()
+    }
+
+    /// Options applied to every query
+    pub fn options(&self) -> &ClaudeCodeOptions {
+        &self.options
+    }
+
+    /// Send a prompt, resuming the conversation's session
+    ///
+    /// The session id is updated as the returned stream reports it.
+    #[instrument(level = "info", skip(self, prompt), fields(prompt_length = prompt.len()))]
+    pub async fn ask(&self, prompt: &str) -> Result<Pin<Box<dyn Stream<Item = Message> + Send>>, ClaudeSDKError> {
+        let mut options = self.options.clone();
+        {
+            let state = self.lock_state();
+            if let Some(session_id) = &state.session_id {
+                debug!(session_id = %session_id, fork = state.fork_pending, "Resuming conversation session");
+                options.resume = Some(session_id.clone());
+                options.continue_conversation = false;
+                options.fork_session = state.fork_pending;
+            }
+        }
+
+        let stream = InternalClient::new().process_query(prompt, options).await?;
+        let state = Arc::clone(&self.state);
+        let stream = stream.map(move |message| {
+            if let Some(session_id) = session_id_of(&message) {
+                let mut state = state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                if state.session_id.as_deref() != Some(session_id) {
+                    info!(session_id, "Conversation moved to session");
+                    state.session_id = Some(session_id.to_string());
+                }
+                // Once the branch exists, later queries resume it normally
+                state.fork_pending = false;
+            }
+            message
+        });
+        Ok(Box::pin(stream))
+    }
+
+    /// Branch off the conversation
+    ///
+    /// The fork starts from the current session, but its first query resumes
+    /// into a new session, leaving this conversation's session untouched.
+    pub fn fork(&self) -> Conversation {
+        let session_id = self.session_id();
+        debug!(session_id = ?session_id, "Forking conversation");
+        Conversation {
+            options: self.options.clone(),
+            state: Arc::new(Mutex::new(SessionState {
+                fork_pending: session_id.is_some(),
+                session_id,
+            })),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, SessionState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Session id a message reports, ignoring placeholder ids of synthesized errors
+fn session_id_of(message: &Message) -> Option<&str> {
+    let session_id = match message {
+        Message::System(system) => system.data.get("session_id")?.as_str()?,
+        Message::Result(result) => result.session_id.as_str(),
+        _ => return None,
+    };
+    (session_id != "error").then_some(session_id)
+}
@@ -1,8 +1,12 @@
 //! Error types for Claude SDK.
 
+use std::path::PathBuf;
+use std::time::Duration;
+
 use thiserror::Error;
-use tracing::error;
+use tracing::{debug, error};
 use crate::config::SafetyError;
+use crate::types::ResultMessage;
 
 /// Base error type for all Claude SDK errors
 #[derive(Error, Debug)]
@@ -24,11 +28,68 @@ pub enum ClaudeSDKError {
     
     #[error("Safety limit violation: {0}")]
     Safety(#[from] SafetyError),
+
+    #[error("Configuration error: {0}")]
+    Config(#[from] ConfigError),
+
+    #[error("Invalid options: {0}")]
+    Validation(#[from] ValidationError),
+    
+    #[error("Maximum turns reached: {0}")]
+    MaxTurnsReached(ResultError),
+    
+    #[error("Execution error: {0}")]
+    Execution(ResultError),
+    
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(ResultError),
+    
+    #[error("Authentication failed: {0}")]
+    Authentication(ApiError),
+    
+    #[error("Rate limited: {0}")]
+    RateLimited(ApiError),
+    
+    #[error("API overloaded: {0}")]
+    Overloaded(ApiError),
+    
+    #[error("Prompt too long: {0}")]
+    PromptTooLong(ApiError),
+    
+    #[error("Invalid model: {0}")]
+    InvalidModel(ApiError),
     
     #[error("Other error: {0}")]
     Other(String),
 }
 
+impl ClaudeSDKError {
+    /// The API error details, if this is one of the API error variants
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            ClaudeSDKError::Authentication(e)
+            | ClaudeSDKError::RateLimited(e)
+            | ClaudeSDKError::Overloaded(e)
+            | ClaudeSDKError::PromptTooLong(e)
+            | ClaudeSDKError::InvalidModel(e) => Some(e),
+            _ => None,
+        }
+    }
+    
+    /// Attach the session id to API error variants
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        match &mut self {
+            ClaudeSDKError::Authentication(e)
+            | ClaudeSDKError::RateLimited(e)
+            | ClaudeSDKError::Overloaded(e)
+            | ClaudeSDKError::PromptTooLong(e)
+            | ClaudeSDKError::InvalidModel(e) => e.session_id = Some(session_id.into()),
+            _ => {}
+        }
+        self
+    }
+}
+
 /// Raised when unable to connect to Claude Code
 #[derive(Error, Debug)]
 #[error("Unable to connect to Claude Code: {message}")]
@@ -177,4 +238,246 @@ impl std::fmt::Display for CLIJSONDecodeError {
         
         write!(f, "Failed to decode JSON: {}", line_preview)
     }
-} 
\ No newline at end of file
+} 
+
+/// Raised when a run ends with an error result
+#[derive(Error, Debug)]
+#[error("{message} (session: {})", result.session_id)]
+pub struct ResultError {
+    pub message: String,
+    /// The result message the run ended with
+    pub result: Box<ResultMessage>,
+}
+
+impl ResultError {
+    pub fn new(message: impl Into<String>, result: ResultMessage) -> Self {
+        let message_str = message.into();
+        error!(
+            message = %message_str,
+            subtype = %result.subtype,
+            session_id = %result.session_id,
+            "Run ended with error result"
+        );
+        Self {
+            message: message_str,
+            result: Box::new(result),
+        }
+    }
+    
+    /// Session the failed run belongs to, for resuming it
+    pub fn session_id(&self) -> &str {
+        &self.result.session_id
+    }
+    
+    /// Token usage reported by the failed run
+    pub fn usage(&self) -> Option<&std::collections::HashMap<String, serde_json::Value>> {
+        self.result.usage.as_ref()
+    }
+}
+
+/// Raised when a configuration file cannot be read or is invalid
+#[derive(Error, Debug)]
+#[error("{message}")]
+pub struct ConfigError {
+    pub message: String,
+    pub path: Option<PathBuf>,
+}
+
+impl ConfigError {
+    pub fn new(message: impl Into<String>) -> Self {
+        let message_str = message.into();
+        error!(message = %message_str, "Configuration error");
+        Self {
+            message: message_str,
+            path: None,
+        }
+    }
+
+    pub fn with_path(message: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let message_str = format!("{}: {}", path.display(), message.into());
+        error!(message = %message_str, "Configuration error");
+        Self {
+            message: message_str,
+            path: Some(path),
+        }
+    }
+}
+
+/// Raised when options conflict or refer to things that do not exist
+#[derive(Error, Debug, Clone)]
+#[error("{}", .problems.join("; "))]
+pub struct ValidationError {
+    /// Every problem found, one sentence each
+    pub problems: Vec<String>,
+}
+
+impl ValidationError {
+    pub fn new(problems: Vec<String>) -> Self {
+        error!(problems = ?problems, "Invalid options");
+        Self { problems }
+    }
+}
+
+/// Raised when the Anthropic API rejects a request made by the CLI
+#[derive(Error, Debug, Clone)]
+#[error("{message}")]
+pub struct ApiError {
+    pub message: String,
+    /// HTTP status reported alongside the error, if any
+    pub status: Option<u16>,
+    /// How long the API asked to wait before retrying
+    pub retry_after: Option<Duration>,
+    /// Session the failing run belongs to, if known
+    pub session_id: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            status: None,
+            retry_after: None,
+            session_id: None,
+        }
+    }
+    
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+}
+
+/// Prefix the CLI puts on result text when the API request failed
+pub(crate) const API_ERROR_PREFIX: &str = "API Error:";
+
+/// Classify API failure text from a result payload or the CLI's stderr
+///
+/// Recognizes the CLI's `API Error: <status> {json}` format first, then falls
+/// back to well-known phrases. Returns `None` when the text does not describe
+/// an authentication, rate limit, overload, prompt length or model error.
+pub fn classify_api_error(text: &str) -> Option<ClaudeSDKError> {
+    let status = parse_api_status(text);
+    let (error_type, api_message) = parse_api_error_body(text);
+    let lower = text.to_lowercase();
+    let api_message_lower = api_message.as_deref().unwrap_or("").to_lowercase();
+    let mentions_model = lower.contains("model");
+
+    let kind = match error_type.as_deref() {
+        Some("authentication_error") | Some("permission_error") => Some(ApiErrorKind::Authentication),
+        Some("rate_limit_error") => Some(ApiErrorKind::RateLimited),
+        Some("overloaded_error") => Some(ApiErrorKind::Overloaded),
+        Some("not_found_error") if api_message_lower.contains("model") => Some(ApiErrorKind::InvalidModel),
+        Some("invalid_request_error") if api_message_lower.contains("prompt is too long") => {
+            Some(ApiErrorKind::PromptTooLong)
+        }
+        Some("invalid_request_error") if api_message_lower.contains("model") => Some(ApiErrorKind::InvalidModel),
+        _ => None,
+    }
+    .or(match status {
+        Some(401) | Some(403) => Some(ApiErrorKind::Authentication),
+        Some(429) => Some(ApiErrorKind::RateLimited),
+        Some(529) => Some(ApiErrorKind::Overloaded),
+        Some(413) => Some(ApiErrorKind::PromptTooLong),
+        Some(404) if mentions_model => Some(ApiErrorKind::InvalidModel),
+        _ => None,
+    })
+    .or_else(|| classify_by_phrase(&lower))?;
+
+    let message = api_message.unwrap_or_else(|| text.trim().to_string());
+    let api_error = ApiError {
+        message,
+        status,
+        retry_after: parse_retry_after(&lower),
+        session_id: None,
+    };
+    debug!(?kind, status = ?api_error.status, "Classified API error");
+    Some(kind.into_error(api_error))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ApiErrorKind {
+    Authentication,
+    RateLimited,
+    Overloaded,
+    PromptTooLong,
+    InvalidModel,
+}
+
+impl ApiErrorKind {
+    fn into_error(self, api_error: ApiError) -> ClaudeSDKError {
+        match self {
+            ApiErrorKind::Authentication => ClaudeSDKError::Authentication(api_error),
+            ApiErrorKind::RateLimited => ClaudeSDKError::RateLimited(api_error),
+            ApiErrorKind::Overloaded => ClaudeSDKError::Overloaded(api_error),
+            ApiErrorKind::PromptTooLong => ClaudeSDKError::PromptTooLong(api_error),
+            ApiErrorKind::InvalidModel => ClaudeSDKError::InvalidModel(api_error),
+        }
+    }
+}
+
+fn classify_by_phrase(lower: &str) -> Option<ApiErrorKind> {
+    const PHRASES: &[(&str, ApiErrorKind)] = &[
+        ("invalid api key", ApiErrorKind::Authentication),
+        ("please run /login", ApiErrorKind::Authentication),
+        ("oauth token has expired", ApiErrorKind::Authentication),
+        ("authentication_error", ApiErrorKind::Authentication),
+        ("rate limit", ApiErrorKind::RateLimited),
+        ("rate_limit", ApiErrorKind::RateLimited),
+        ("too many requests", ApiErrorKind::RateLimited),
+        ("overloaded", ApiErrorKind::Overloaded),
+        ("prompt is too long", ApiErrorKind::PromptTooLong),
+        ("context length", ApiErrorKind::PromptTooLong),
+        ("context window", ApiErrorKind::PromptTooLong),
+        ("invalid model", ApiErrorKind::InvalidModel),
+        ("model not found", ApiErrorKind::InvalidModel),
+        ("issue with the selected model", ApiErrorKind::InvalidModel),
+    ];
+    PHRASES
+        .iter()
+        .find(|(phrase, _)| lower.contains(phrase))
+        .map(|(_, kind)| *kind)
+}
+
+/// Status code from the CLI's `API Error: <status>` prefix
+fn parse_api_status(text: &str) -> Option<u16> {
+    let rest = &text[text.find(API_ERROR_PREFIX)? + API_ERROR_PREFIX.len()..];
+    let digits: String = rest.trim_start().chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.len() == 3 {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// `error.type` and `error.message` from the first JSON object in `text`
+fn parse_api_error_body(text: &str) -> (Option<String>, Option<String>) {
+    let Some(start) = text.find('{') else {
+        return (None, None);
+    };
+    let body = serde_json::Deserializer::from_str(&text[start..])
+        .into_iter::<serde_json::Value>()
+        .next()
+        .and_then(Result::ok);
+    let error = body.as_ref().and_then(|body| body.get("error"));
+    let field = |name: &str| {
+        error
+            .and_then(|error| error.get(name))
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+    };
+    (field("type"), field("message"))
+}
+
+/// Delay from a `retry-after: <seconds>` or `retry after <seconds>` hint
+fn parse_retry_after(lower: &str) -> Option<Duration> {
+    ["retry-after", "retry after", "retry_after"].iter().find_map(|marker| {
+        let rest = &lower[lower.find(marker)? + marker.len()..];
+        let digits: String = rest
+            .trim_start_matches(|c: char| c == ':' || c == '"' || c.is_whitespace())
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        digits.parse().ok().map(Duration::from_secs)
+    })
+}
@@ -0,0 +1,203 @@
+//! Persistent cost and usage ledger
+//!
+//! A [`Ledger`] appends one JSON line per completed query to a local file and
+//! aggregates the records by day, model or project.
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::ops::AddAssign;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::errors::{ClaudeSDKError, ConfigError};
+use crate::pricing::Usage;
+use crate::types::ResultMessage;
+
+/// One completed query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerRecord {
+    /// When the query finished
+    pub timestamp: DateTime<Utc>,
+    pub session_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Working directory the query ran in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    #[serde(default)]
+    pub usage: Usage,
+    /// Cost in US dollars, when reported or priced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    /// Whether `cost_usd` is the SDK's estimate rather than the CLI's billed figure
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cost_is_estimate: bool,
+    pub duration_ms: u64,
+    pub num_turns: u32,
+    /// Result subtype, e.g. `success` or `error_max_turns`
+    pub subtype: String,
+    pub is_error: bool,
+}
+
+impl LedgerRecord {
+    /// Build a record for a query that just finished with `result`
+    pub fn from_result(result: &ResultMessage, cwd: Option<&Path>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session_id: result.session_id.clone(),
+            model: result.model.clone(),
+            cwd: cwd.map(Path::to_path_buf),
+            usage: result.typed_usage().unwrap_or_default(),
+            cost_usd: result.total_cost_usd.or(result.estimated_cost_usd),
+            cost_is_estimate: result.total_cost_usd.is_none() && result.estimated_cost_usd.is_some(),
+            duration_ms: result.duration_ms,
+            num_turns: result.num_turns,
+            subtype: result.subtype.clone(),
+            is_error: result.is_error,
+        }
+    }
+
+    /// UTC day of the record, as `YYYY-MM-DD`
+    pub fn day(&self) -> String {
+        self.timestamp.format("%Y-%m-%d").to_string()
+    }
+}
+
+/// Aggregated figures for a group of records
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LedgerTotals {
+    pub queries: u64,
+    pub errors: u64,
+    pub usage: Usage,
+    pub cost_usd: f64,
+    pub duration_ms: u64,
+    pub num_turns: u64,
+}
+
+impl AddAssign<&LedgerRecord> for LedgerTotals {
+    fn add_assign(&mut self, record: &LedgerRecord) {
+        self.queries += 1;
+        self.errors += u64::from(record.is_error);
+        self.usage += record.usage;
+        self.cost_usd += record.cost_usd.unwrap_or(0.0);
+        self.duration_ms += record.duration_ms;
+        self.num_turns += u64::from(record.num_turns);
+    }
+}
+
+/// Sum `records` grouped by `key`
+pub fn totals_by<'a, K, F>(records: impl IntoIterator<Item = &'a LedgerRecord>, key: F) -> BTreeMap<K, LedgerTotals>
+where
+    K: Ord,
+    F: Fn(&LedgerRecord) -> K,
+{
+    let mut totals: BTreeMap<K, LedgerTotals> = BTreeMap::new();
+    for record in records {
+        *totals.entry(key(record)).or_default() += record;
+    }
+    totals
+}
+
+/// Append-only JSONL file of [`LedgerRecord`]s
+///
+/// Serializes as the path of the file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Ledger {
+    path: PathBuf,
+}
+
+impl Ledger {
+    /// Use the ledger file at `path`, which is created on first append
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The ledger file's path
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append one record
+    pub fn append(&self, record: &LedgerRecord) -> Result<(), ClaudeSDKError> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| ConfigError::with_path(format!("Failed to serialize ledger record: {}", e), &self.path))?;
+        line.push('\n');
+
+        // A single write keeps concurrent appenders from interleaving lines
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        debug!(
+            path = %self.path.display(),
+            session_id = %record.session_id,
+            cost_usd = ?record.cost_usd,
+            "Appended ledger record"
+        );
+        Ok(())
+    }
+
+    /// Read every record, skipping lines that cannot be parsed
+    pub fn records(&self) -> Result<Vec<LedgerRecord>, ClaudeSDKError> {
+        let file = match std::fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut records = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(e) => warn!(line = index + 1, error = %e, "Skipping malformed ledger line"),
+            }
+        }
+        info!(path = %self.path.display(), records = records.len(), "Read ledger");
+        Ok(records)
+    }
+
+    /// Records with a timestamp in `[start, end)`
+    pub fn records_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<LedgerRecord>, ClaudeSDKError> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .filter(|record| record.timestamp >= start && record.timestamp < end)
+            .collect())
+    }
+
+    /// Totals over every record
+    pub fn total(&self) -> Result<LedgerTotals, ClaudeSDKError> {
+        let mut totals = LedgerTotals::default();
+        for record in &self.records()? {
+            totals += record;
+        }
+        Ok(totals)
+    }
+
+    /// Totals per UTC day (`YYYY-MM-DD`)
+    pub fn totals_by_day(&self) -> Result<BTreeMap<String, LedgerTotals>, ClaudeSDKError> {
+        Ok(totals_by(&self.records()?, LedgerRecord::day))
+    }
+
+    /// Totals per model, with `unknown` for records without one
+    pub fn totals_by_model(&self) -> Result<BTreeMap<String, LedgerTotals>, ClaudeSDKError> {
+        Ok(totals_by(&self.records()?, |record| {
+            record.model.clone().unwrap_or_else(|| "unknown".to_string())
+        }))
+    }
+
+    /// Totals per project directory, with `None` for records without one
+    pub fn totals_by_project(&self) -> Result<BTreeMap<Option<PathBuf>, LedgerTotals>, ClaudeSDKError> {
+        Ok(totals_by(&self.records()?, |record| record.cwd.clone()))
+    }
+}
@@ -14,6 +14,7 @@
 //!     .init();
 //! ```
 
+use std::collections::HashMap;
 use std::pin::Pin;
 use futures::Stream;
 use tracing::{debug, info, instrument};
@@ -21,12 +22,34 @@ use tracing::{debug, info, instrument};
 pub mod types;
 pub mod errors;
 pub mod config;
+pub mod config_dir;
+pub mod builder;
+pub mod profile;
+pub mod mcp;
 mod client;
 pub mod transport;
+pub mod retry;
+pub mod pricing;
+pub mod ledger;
+pub mod sessions;
+pub mod conversation;
+pub mod render;
+pub mod wire;
 
 pub use types::*;
 pub use errors::*;
 pub use config::*;
+pub use config_dir::ConfigDir;
+pub use builder::ClaudeCodeOptionsBuilder;
+pub use profile::{Profile, ProfileFormat};
+pub use mcp::{McpHttpServerConfig, McpSdkServerConfig, McpServerConfig, McpSseServerConfig, McpStdioServerConfig};
+pub use retry::{RetryOn, RetryPolicy};
+pub use pricing::{ModelPricing, PricingTable, Usage};
+pub use ledger::{Ledger, LedgerRecord, LedgerTotals};
+pub use conversation::Conversation;
+pub use render::{render_html, render_markdown, RenderOptions};
+pub use sessions::{RetentionPolicy, SearchIndex, SessionInfo, SessionStore, Transcript, TranscriptEntry};
+pub use wire::parse_message_line;
 
 use client::InternalClient;
 
@@ -98,10 +121,6 @@ pub async fn query(
     let options = options.unwrap_or_default();
     debug!(?options, "Using query options");
     
-    // Set environment variable
-    std::env::set_var("CLAUDE_CODE_ENTRYPOINT", "sdk-rust");
-    debug!("Set CLAUDE_CODE_ENTRYPOINT environment variable");
-    
     let client = InternalClient::new();
     info!("Created internal client");
     
@@ -111,6 +130,64 @@ pub async fn query(
     Ok(Box::pin(stream))
 }
 
+/// Query Claude Code, yielding each message as the CLI emitted it.
+/// 
+/// Works like [`query`], but every item carries the decoded JSON object from
+/// the CLI's `stream-json` output alongside its typed form. Message types the
+/// SDK does not model are still yielded, with `message` set to `None`.
+/// 
+/// # Example
+/// 
+/// ```rust,no_run
+/// use claude_code_sdk::query_raw;
+/// use tokio_stream::StreamExt;
+/// 
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut stream = query_raw("Hello", None).await?;
+///     while let Some(item) = stream.next().await {
+///         println!("{}", item.raw);
+///         if let Some(message) = item.message {
+///             println!("{:?}", message);
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[instrument(
+    level = "info",
+    skip(options),
+    fields(
+        prompt_length = prompt.len(),
+        has_options = options.is_some(),
+    )
+)]
+pub async fn query_raw(
+    prompt: &str,
+    options: Option<ClaudeCodeOptions>,
+) -> Result<Pin<Box<dyn Stream<Item = RawMessage> + Send>>, ClaudeSDKError> {
+    info!("Starting raw Claude Code query");
+    
+    let options = options.unwrap_or_default();
+    debug!(?options, "Using query options");
+    
+    let client = InternalClient::new();
+    let stream = client.process_query_raw(prompt, options).await?;
+    info!("Successfully created raw message stream");
+    
+    Ok(stream)
+}
+
+/// Parse a decoded CLI message into a typed [`Message`].
+/// 
+/// `data` is one JSON object as yielded by [`transport::Transport::receive_messages`].
+/// Returns `None` for message types the SDK does not model or when required
+/// fields are missing. To parse raw output lines without building a map first,
+/// use [`parse_message_line`].
+pub fn parse_message(data: HashMap<String, serde_json::Value>) -> Option<Message> {
+    InternalClient::parse_message(&data)
+}
+
 /// Initialize default tracing subscriber for development and testing.
 /// 
 /// This is a convenience function that sets up a basic tracing subscriber
@@ -0,0 +1,208 @@
+//! MCP server configuration
+//!
+//! [`McpServerConfig`] mirrors one entry of the CLI's `mcpServers` table, so
+//! options serialize to exactly what `--mcp-config` expects and servers can
+//! be loaded from an existing `.mcp.json`. Keys the SDK does not model are
+//! kept in each server's `extra` map and passed on unchanged.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+use crate::errors::ConfigError;
+
+/// How the CLI reaches an MCP server
+///
+/// Serialized with a `type` tag. Entries without one are read as stdio
+/// servers, as in `.mcp.json`.
+///
+/// ```rust
+/// use claude_code_sdk::McpServerConfig;
+///
+/// let github = McpServerConfig::stdio("gh-mcp", ["--stdio"]).with_env("GITHUB_TOKEN", "ghp_example");
+/// let docs = McpServerConfig::http("https://docs.example.com/mcp").with_header("Authorization", "Bearer token");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum McpServerConfig {
+    /// A local command speaking MCP over stdin and stdout
+    Stdio(McpStdioServerConfig),
+    /// A remote server using server-sent events
+    Sse(McpSseServerConfig),
+    /// A remote server using streamable HTTP
+    Http(McpHttpServerConfig),
+    /// A server hosted by the SDK process, reached over the CLI's control protocol
+    ///
+    /// Not served yet: options using it fail validation.
+    Sdk(McpSdkServerConfig),
+}
+
+/// A local command speaking MCP over stdin and stdout
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpStdioServerConfig {
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Variables set for the server, on top of the CLI's environment
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Other keys, passed to the CLI as they are
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// A remote server using server-sent events
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpSseServerConfig {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Other keys, passed to the CLI as they are
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// A remote server using streamable HTTP
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpHttpServerConfig {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Other keys, passed to the CLI as they are
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// A server hosted by the SDK process
+///
+/// The CLI forwards its tool calls to the process that started it, which
+/// has to answer them. This SDK cannot yet, so options with such a server
+/// fail validation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpSdkServerConfig {
+    pub name: String,
+    /// Other keys, passed to the CLI as they are
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, Value>,
+}
+
+/// Mirror of [`McpServerConfig`] whose tag is required
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Tagged {
+    Stdio(McpStdioServerConfig),
+    Sse(McpSseServerConfig),
+    Http(McpHttpServerConfig),
+    Sdk(McpSdkServerConfig),
+}
+
+impl<'de> Deserialize<'de> for McpServerConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        if let Value::Object(fields) = &mut value {
+            fields.entry("type").or_insert_with(|| Value::from("stdio"));
+        }
+        let tagged = Tagged::deserialize(value).map_err(serde::de::Error::custom)?;
+        Ok(match tagged {
+            Tagged::Stdio(config) => McpServerConfig::Stdio(config),
+            Tagged::Sse(config) => McpServerConfig::Sse(config),
+            Tagged::Http(config) => McpServerConfig::Http(config),
+            Tagged::Sdk(config) => McpServerConfig::Sdk(config),
+        })
+    }
+}
+
+impl McpServerConfig {
+    /// A stdio server started with `command` and `args`
+    pub fn stdio<I, S>(command: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        McpServerConfig::Stdio(McpStdioServerConfig {
+            command: command.into(),
+            args: args.into_iter().map(Into::into).collect(),
+            env: BTreeMap::new(),
+            extra: BTreeMap::new(),
+        })
+    }
+
+    /// An SSE server at `url`
+    pub fn sse(url: impl Into<String>) -> Self {
+        McpServerConfig::Sse(McpSseServerConfig {
+            url: url.into(),
+            headers: BTreeMap::new(),
+            extra: BTreeMap::new(),
+        })
+    }
+
+    /// A streamable HTTP server at `url`
+    pub fn http(url: impl Into<String>) -> Self {
+        McpServerConfig::Http(McpHttpServerConfig {
+            url: url.into(),
+            headers: BTreeMap::new(),
+            extra: BTreeMap::new(),
+        })
+    }
+
+    /// A server hosted by the SDK process under `name`
+    ///
+    /// Not served yet: options using it fail validation.
+    pub fn sdk(name: impl Into<String>) -> Self {
+        McpServerConfig::Sdk(McpSdkServerConfig {
+            name: name.into(),
+            extra: BTreeMap::new(),
+        })
+    }
+
+    /// Set a variable for a stdio server; other servers are unchanged
+    pub fn with_env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        if let McpServerConfig::Stdio(config) = &mut self {
+            config.env.insert(name.into(), value.into());
+        }
+        self
+    }
+
+    /// Send a header to an SSE or HTTP server; other servers are unchanged
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        if let McpServerConfig::Sse(McpSseServerConfig { headers, .. })
+        | McpServerConfig::Http(McpHttpServerConfig { headers, .. }) = &mut self
+        {
+            headers.insert(name.into(), value.into());
+        }
+        self
+    }
+
+    /// The `type` tag the CLI uses for this server
+    pub fn kind(&self) -> &'static str {
+        match self {
+            McpServerConfig::Stdio(_) => "stdio",
+            McpServerConfig::Sse(_) => "sse",
+            McpServerConfig::Http(_) => "http",
+            McpServerConfig::Sdk(_) => "sdk",
+        }
+    }
+
+    /// Read the servers from an `.mcp.json` file
+    ///
+    /// The file holds an `mcpServers` table, as written by `claude mcp add
+    /// --scope project`. `${VAR}` references are passed on unchanged.
+    pub fn load(path: impl AsRef<Path>) -> Result<HashMap<String, McpServerConfig>, ConfigError> {
+        #[derive(Deserialize)]
+        struct McpFile {
+            #[serde(rename = "mcpServers", default)]
+            mcp_servers: HashMap<String, McpServerConfig>,
+        }
+
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::with_path(format!("Failed to read MCP config: {}", e), path))?;
+        let file: McpFile = serde_json::from_str(&contents)
+            .map_err(|e| ConfigError::with_path(format!("Invalid MCP config: {}", e), path))?;
+        info!(path = %path.display(), servers = file.mcp_servers.len(), "Loaded MCP servers");
+        Ok(file.mcp_servers)
+    }
+}
@@ -0,0 +1,229 @@
+//! Model pricing and cost computation
+
+use std::collections::HashMap;
+use std::ops::AddAssign;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use crate::errors::{ClaudeSDKError, ConfigError};
+
+/// Token counts reported by the API for one or more requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+}
+
+impl Usage {
+    /// Read token counts from a CLI usage object, ignoring unknown fields
+    pub fn from_value(value: &serde_json::Value) -> Option<Self> {
+        Self::deserialize(value).ok()
+    }
+
+    /// Read token counts from a usage map such as [`crate::ResultMessage::usage`]
+    pub fn from_map(map: &HashMap<String, serde_json::Value>) -> Self {
+        let tokens = |key: &str| map.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        Self {
+            input_tokens: tokens("input_tokens"),
+            output_tokens: tokens("output_tokens"),
+            cache_creation_input_tokens: tokens("cache_creation_input_tokens"),
+            cache_read_input_tokens: tokens("cache_read_input_tokens"),
+        }
+    }
+
+    /// Convert into a usage map in the CLI's format
+    pub fn to_map(&self) -> HashMap<String, serde_json::Value> {
+        HashMap::from([
+            ("input_tokens".to_string(), self.input_tokens.into()),
+            ("output_tokens".to_string(), self.output_tokens.into()),
+            ("cache_creation_input_tokens".to_string(), self.cache_creation_input_tokens.into()),
+            ("cache_read_input_tokens".to_string(), self.cache_read_input_tokens.into()),
+        ])
+    }
+}
+
+impl AddAssign for Usage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
+}
+
+/// Prices for one model, in US dollars per million tokens
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+    pub cache_write_per_mtok: f64,
+    pub cache_read_per_mtok: f64,
+}
+
+impl ModelPricing {
+    /// Create pricing from per-million-token prices
+    pub const fn new(input: f64, output: f64, cache_write: f64, cache_read: f64) -> Self {
+        Self {
+            input_per_mtok: input,
+            output_per_mtok: output,
+            cache_write_per_mtok: cache_write,
+            cache_read_per_mtok: cache_read,
+        }
+    }
+
+    /// Cost of `usage` in US dollars
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_mtok
+            + usage.output_tokens as f64 * self.output_per_mtok
+            + usage.cache_creation_input_tokens as f64 * self.cache_write_per_mtok
+            + usage.cache_read_input_tokens as f64 * self.cache_read_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// Per-model price table
+///
+/// Models are matched by the longest key that prefixes the model name, so
+/// `claude-sonnet-4-5` also prices dated names like `claude-sonnet-4-5-20250929`.
+///
+/// Tables can be loaded from JSON files of the form:
+///
+/// ```json
+/// {
+///   "models": {
+///     "claude-sonnet-4-5": {
+///       "input_per_mtok": 3.0,
+///       "output_per_mtok": 15.0,
+///       "cache_write_per_mtok": 3.75,
+///       "cache_read_per_mtok": 0.3
+///     }
+///   },
+///   "fallback": null
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricingTable {
+    /// Prices keyed by model name or name prefix
+    #[serde(default)]
+    pub models: HashMap<String, ModelPricing>,
+
+    /// Prices for models missing from the table (unpriced when `None`)
+    #[serde(default)]
+    pub fallback: Option<ModelPricing>,
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        let opus_4 = ModelPricing::new(15.0, 75.0, 18.75, 1.50);
+        let opus_4_5 = ModelPricing::new(5.0, 25.0, 6.25, 0.50);
+        let sonnet = ModelPricing::new(3.0, 15.0, 3.75, 0.30);
+        let haiku_4_5 = ModelPricing::new(1.0, 5.0, 1.25, 0.10);
+        let haiku_3_5 = ModelPricing::new(0.80, 4.0, 1.0, 0.08);
+        let haiku_3 = ModelPricing::new(0.25, 1.25, 0.30, 0.03);
+
+        let models = [
+            ("claude-opus-4-5", opus_4_5),
+            ("claude-opus-4-1", opus_4),
+            ("claude-opus-4", opus_4),
+            ("claude-3-opus", opus_4),
+            ("claude-sonnet-4-5", sonnet),
+            ("claude-sonnet-4", sonnet),
+            ("claude-3-7-sonnet", sonnet),
+            ("claude-3-5-sonnet", sonnet),
+            ("claude-haiku-4-5", haiku_4_5),
+            ("claude-3-5-haiku", haiku_3_5),
+            ("claude-3-haiku", haiku_3),
+            // Aliases accepted by the CLI's --model flag
+            ("opus", opus_4),
+            ("sonnet", sonnet),
+            ("haiku", haiku_4_5),
+        ];
+
+        Self {
+            models: models
+                .into_iter()
+                .map(|(name, pricing)| (name.to_string(), pricing))
+                .collect(),
+            fallback: None,
+        }
+    }
+}
+
+impl PricingTable {
+    /// Create the built-in price table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a table with no prices
+    pub fn empty() -> Self {
+        Self {
+            models: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    /// Load a complete price table from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ClaudeSDKError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::with_path(format!("Failed to read pricing file: {}", e), path))?;
+        let table: Self = serde_json::from_str(&contents)
+            .map_err(|e| ConfigError::with_path(format!("Invalid pricing file: {}", e), path))?;
+        info!(path = %path.display(), models = table.models.len(), "Loaded pricing table");
+        Ok(table)
+    }
+
+    /// Built-in prices with the entries of a JSON file layered on top
+    pub fn with_overrides_from_file(path: impl AsRef<Path>) -> Result<Self, ClaudeSDKError> {
+        let mut table = Self::default();
+        table.merge(Self::from_file(path)?);
+        Ok(table)
+    }
+
+    /// Add every entry of `other`, replacing prices for models in both tables
+    pub fn merge(&mut self, other: PricingTable) {
+        debug!(models = other.models.len(), "Merging pricing overrides");
+        self.models.extend(other.models);
+        if other.fallback.is_some() {
+            self.fallback = other.fallback;
+        }
+    }
+
+    /// Add or replace the prices for a model
+    pub fn with_model(mut self, model: impl Into<String>, pricing: ModelPricing) -> Self {
+        self.models.insert(model.into(), pricing);
+        self
+    }
+
+    /// Look up the prices for a model
+    pub fn lookup(&self, model: &str) -> Option<&ModelPricing> {
+        let found = self
+            .models
+            .iter()
+            .filter(|(name, _)| model.starts_with(name.as_str()))
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, pricing)| pricing)
+            .or(self.fallback.as_ref());
+        if found.is_none() {
+            // Callers decide whether a miss is worth a warning; most see the same model repeatedly
+            debug!(model, "No pricing known for model");
+        }
+        found
+    }
+
+    /// Cost of `usage` on `model` in US dollars, if the model is priced
+    pub fn cost(&self, model: &str, usage: &Usage) -> Option<f64> {
+        let cost = self.lookup(model)?.cost(usage);
+        debug!(model, cost_usd = cost, "Computed usage cost");
+        Some(cost)
+    }
+}
@@ -0,0 +1,289 @@
+//! Loading options from profile files and environment variables
+//!
+//! A [`Profile`] is a partial set of [`ClaudeCodeOptions`] fields. Profiles
+//! read from TOML, JSON or YAML files and from `CLAUDE_SDK_*` environment
+//! variables can be layered with [`Profile::merge`], so a shared base
+//! profile can be combined with per-job overrides before building options.
+
+use std::path::Path;
+
+use serde_json::{Map, Value};
+use tracing::{debug, info, warn};
+
+use crate::errors::ConfigError;
+use crate::types::ClaudeCodeOptions;
+
+/// Prefix of environment variables that override options
+pub const ENV_PREFIX: &str = "CLAUDE_SDK_";
+
+/// How an environment variable's value maps onto an option field
+#[derive(Debug, Clone, Copy)]
+enum EnvKind {
+    String,
+    Bool,
+    Integer,
+    Float,
+    /// Comma-separated list
+    List,
+}
+
+/// Option fields that can be set from `CLAUDE_SDK_<FIELD>` variables
+const ENV_FIELDS: [(&str, EnvKind); 24] = [
+    ("model", EnvKind::String),
+    ("fallback_models", EnvKind::List),
+    ("system_prompt", EnvKind::String),
+    ("append_system_prompt", EnvKind::String),
+    ("allowed_tools", EnvKind::List),
+    ("disallowed_tools", EnvKind::List),
+    ("mcp_tools", EnvKind::List),
+    ("permission_mode", EnvKind::String),
+    ("permission_prompt_tool_name", EnvKind::String),
+    ("max_turns", EnvKind::Integer),
+    ("max_thinking_tokens", EnvKind::Integer),
+    ("max_budget_usd", EnvKind::Float),
+    ("cwd", EnvKind::String),
+    ("cli_path", EnvKind::String),
+    ("add_dirs", EnvKind::List),
+    ("settings", EnvKind::String),
+    ("setting_sources", EnvKind::List),
+    ("strict_mcp_config", EnvKind::Bool),
+    ("user", EnvKind::String),
+    ("env_clear", EnvKind::Bool),
+    ("env_allowlist", EnvKind::List),
+    ("resume", EnvKind::String),
+    ("continue_conversation", EnvKind::Bool),
+    ("fork_session", EnvKind::Bool),
+];
+
+/// File format of a profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+
+impl ProfileFormat {
+    /// Format implied by a file's extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "toml" => Some(ProfileFormat::Toml),
+            "json" => Some(ProfileFormat::Json),
+            "yaml" | "yml" => Some(ProfileFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// A partial set of option fields
+///
+/// ```rust,no_run
+/// use claude_code_sdk::profile::Profile;
+///
+/// # fn example() -> Result<(), claude_code_sdk::ConfigError> {
+/// let options = Profile::from_file("profiles/base.toml")?
+///     .merge(Profile::from_file("profiles/nightly-review.yaml")?)
+///     .merge(Profile::from_env()?)
+///     .to_options()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    values: Map<String, Value>,
+}
+
+impl Profile {
+    /// An empty profile, which builds the default options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a profile in the given format
+    pub fn parse(contents: &str, format: ProfileFormat) -> Result<Self, ConfigError> {
+        let values = parse_values(contents, format).map_err(ConfigError::new)?;
+        Ok(Self { values })
+    }
+
+    /// Read a profile, choosing the format from the file extension
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let format = ProfileFormat::from_path(path).ok_or_else(|| {
+            ConfigError::with_path("Unknown profile format, expected .toml, .json, .yaml or .yml", path)
+        })?;
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::with_path(format!("Failed to read profile: {}", e), path))?;
+        let profile = Self {
+            values: parse_values(&contents, format).map_err(|e| ConfigError::with_path(e, path))?,
+        };
+        info!(path = %path.display(), fields = profile.values.len(), "Loaded options profile");
+        Ok(profile)
+    }
+
+    /// Overrides from `CLAUDE_SDK_*` variables in the process environment
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Overrides from `CLAUDE_SDK_*` entries among `vars`
+    ///
+    /// `CLAUDE_SDK_MODEL` sets `model`, `CLAUDE_SDK_MAX_TURNS` sets
+    /// `max_turns`, and so on. List fields such as `allowed_tools` take
+    /// comma-separated values. Other variables are ignored.
+    pub fn from_vars<I, K, V>(vars: I) -> Result<Self, ConfigError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut values = Map::new();
+        for (name, raw) in vars {
+            let (name, raw) = (name.as_ref(), raw.as_ref());
+            let Some(field) = name.strip_prefix(ENV_PREFIX).map(str::to_ascii_lowercase) else {
+                continue;
+            };
+            let Some((field, kind)) = ENV_FIELDS.iter().find(|(f, _)| *f == field) else {
+                warn!(variable = name, "Ignoring unknown options variable");
+                continue;
+            };
+            let invalid = |expected: &str| ConfigError::new(format!("{} must be {}, got {:?}", name, expected, raw));
+            let value = match kind {
+                EnvKind::String => Value::from(raw),
+                EnvKind::Bool => match raw.trim().to_ascii_lowercase().as_str() {
+                    "1" | "true" | "yes" | "on" => Value::Bool(true),
+                    "0" | "false" | "no" | "off" | "" => Value::Bool(false),
+                    _ => return Err(invalid("a boolean")),
+                },
+                EnvKind::Integer => Value::from(raw.trim().parse::<u64>().map_err(|_| invalid("a whole number"))?),
+                EnvKind::Float => Value::from(raw.trim().parse::<f64>().map_err(|_| invalid("a number"))?),
+                EnvKind::List => Value::from(
+                    raw.split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .collect::<Vec<_>>(),
+                ),
+            };
+            debug!(variable = name, field, "Option overridden from environment");
+            values.insert(field.to_string(), value);
+        }
+        Ok(Self { values })
+    }
+
+    /// Every field of `options`
+    pub fn from_options(options: &ClaudeCodeOptions) -> Self {
+        match serde_json::to_value(options) {
+            Ok(Value::Object(values)) => Self { values },
+            _ => unreachable!("options serialize to a map"),
+        }
+    }
+
+    /// Set one field
+    pub fn with(mut self, field: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.values.insert(field.into(), value.into());
+        self
+    }
+
+    /// The fields set by this profile
+    pub fn values(&self) -> &Map<String, Value> {
+        &self.values
+    }
+
+    /// Layer `overrides` on top of this profile
+    ///
+    /// Tables such as `retry_policy` are merged key by key; any other value,
+    /// including lists, is replaced. Named entries of `mcp_servers`, `env`
+    /// and `extra_args` are replaced whole, so an override can switch a
+    /// server to another transport. A top-level `null` resets the field to
+    /// its default, while a nested one is kept, e.g. to remove a variable
+    /// through `env`.
+    pub fn merge(mut self, overrides: Profile) -> Self {
+        merge_maps(&mut self.values, overrides.values);
+        self
+    }
+
+    /// Build options from the profile, using defaults for unset fields
+    pub fn to_options(&self) -> Result<ClaudeCodeOptions, ConfigError> {
+        serde_json::from_value(Value::Object(self.values.clone()))
+            .map_err(|e| ConfigError::new(format!("Invalid options: {}", e)))
+    }
+
+    /// Write the profile in the given format
+    pub fn serialize_as(&self, format: ProfileFormat) -> Result<String, ConfigError> {
+        match format {
+            ProfileFormat::Toml => toml::to_string_pretty(&strip_nulls(&self.values)).map_err(|e| e.to_string()),
+            ProfileFormat::Json => serde_json::to_string_pretty(&self.values).map_err(|e| e.to_string()),
+            ProfileFormat::Yaml => serde_yaml::to_string(&self.values).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| ConfigError::new(format!("Failed to serialize {:?} profile: {}", format, e)))
+    }
+}
+
+impl ClaudeCodeOptions {
+    /// Load options from profile files, later files overriding earlier ones,
+    /// then apply `CLAUDE_SDK_*` environment overrides
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<Self, ConfigError> {
+        let mut profile = Profile::new();
+        for path in paths {
+            profile = profile.merge(Profile::from_file(path)?);
+        }
+        profile.merge(Profile::from_env()?).to_options()
+    }
+}
+
+fn parse_values(contents: &str, format: ProfileFormat) -> Result<Map<String, Value>, String> {
+    let value: Value = match format {
+        ProfileFormat::Toml => toml::from_str(contents).map_err(|e| e.to_string()),
+        ProfileFormat::Json => serde_json::from_str(contents).map_err(|e| e.to_string()),
+        ProfileFormat::Yaml => serde_yaml::from_str(contents).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| format!("Invalid {:?} profile: {}", format, e))?;
+
+    match value {
+        Value::Object(values) => Ok(values),
+        // An empty YAML document
+        Value::Null => Ok(Map::new()),
+        other => Err(format!("Profile must be a table of options, got {}", other)),
+    }
+}
+
+/// Option fields holding named entries that are each replaced whole
+const ENTRY_FIELDS: [&str; 3] = ["mcp_servers", "env", "extra_args"];
+
+/// Merge `overrides` into `base`; a top-level `null` removes the field
+fn merge_maps(base: &mut Map<String, Value>, overrides: Map<String, Value>) {
+    for (key, value) in overrides {
+        if value.is_null() {
+            base.remove(&key);
+            continue;
+        }
+        let slot = base.entry(key.as_str()).or_insert(Value::Null);
+        match (ENTRY_FIELDS.contains(&key.as_str()), slot, value) {
+            (true, Value::Object(entries), Value::Object(overrides)) => entries.extend(overrides),
+            (_, slot, value) => merge_value(slot, value),
+        }
+    }
+}
+
+/// Merge tables key by key and replace everything else, including with `null`
+fn merge_value(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge_value(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// TOML has no null, so unset fields are left out
+fn strip_nulls(values: &Map<String, Value>) -> Map<String, Value> {
+    values
+        .iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| match value {
+            Value::Object(inner) => (key.clone(), Value::Object(strip_nulls(inner))),
+            other => (key.clone(), other.clone()),
+        })
+        .collect()
+}
@@ -0,0 +1,638 @@
+//! Rendering conversations as Markdown or HTML
+//!
+//! Both renderers work on a `Vec<Message>` from a live stream or on a
+//! loaded [`Transcript`]. Tool calls become collapsible sections holding
+//! their input and output, with `Edit` and `MultiEdit` inputs shown as
+//! diffs and long outputs truncated. Each [`ResultMessage`] becomes a
+//! footer with its usage and cost.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use tracing::debug;
+
+use crate::pricing::PricingTable;
+use crate::sessions::Transcript;
+use crate::types::*;
+
+/// Tool input fields describing what a call acts on, in order of preference
+const LABEL_FIELDS: [&str; 8] = [
+    "file_path",
+    "notebook_path",
+    "command",
+    "pattern",
+    "url",
+    "query",
+    "path",
+    "description",
+];
+
+/// Longest tool label shown in a collapsed tool call, in characters
+const MAX_LABEL_CHARS: usize = 80;
+
+const HTML_STYLE: &str = "\
+body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Helvetica,Arial,sans-serif;margin:0;background:#f6f8fa;color:#1f2328}
+main{max-width:900px;margin:0 auto;padding:24px}
+h1{font-size:1.5em}
+section{background:#fff;border:1px solid #d0d7de;border-radius:6px;margin:16px 0;padding:12px 16px}
+section.user{border-left:4px solid #0969da}
+section.assistant{border-left:4px solid #8250df}
+h2{font-size:.85em;text-transform:uppercase;letter-spacing:.05em;color:#59636e;margin:0 0 8px}
+.text{white-space:pre-wrap;line-height:1.5}
+details{border:1px solid #d0d7de;border-radius:6px;margin:8px 0;background:#f6f8fa}
+summary{cursor:pointer;padding:6px 10px;font-family:ui-monospace,SFMono-Regular,Menlo,monospace;font-size:.9em}
+details>div{padding:0 10px 10px}
+pre{background:#fff;border:1px solid #d0d7de;border-radius:4px;padding:8px;overflow-x:auto;font-size:.85em;margin:6px 0}
+.label{font-size:.8em;font-weight:600;color:#59636e;margin-top:8px}
+.diff span{display:block}
+.add{background:#dafbe1}
+.del{background:#ffebe9}
+.error{border-color:#cf222e}
+footer{border-top:1px solid #d0d7de;color:#59636e;font-size:.9em;padding:8px 0;margin:16px 0}
+";
+
+/// Options for [`render_markdown`] and [`render_html`]
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Heading for the document; transcripts default to their summary or first prompt
+    pub title: Option<String>,
+
+    /// Longest tool output shown before it is cut off, in characters
+    pub max_tool_result_chars: usize,
+
+    /// Longest tool input shown before it is cut off, in characters
+    ///
+    /// Diffs of `Edit` and `MultiEdit` inputs are always shown in full.
+    pub max_tool_input_chars: usize,
+
+    /// Prices usage for footers of results that do not report their cost
+    pub pricing: PricingTable,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            max_tool_result_chars: 2000,
+            max_tool_input_chars: 2000,
+            pricing: PricingTable::default(),
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Default options with a document title
+    pub fn with_title(title: impl Into<String>) -> Self {
+        Self {
+            title: Some(title.into()),
+            ..Self::default()
+        }
+    }
+}
+
+/// Render messages as GitHub-flavored Markdown
+pub fn render_markdown(messages: &[Message], options: &RenderOptions) -> String {
+    let items = items_from_messages(messages);
+    markdown(&Document::build(&items, options), options)
+}
+
+/// Render messages as a self-contained HTML page
+pub fn render_html(messages: &[Message], options: &RenderOptions) -> String {
+    let items = items_from_messages(messages);
+    html(&Document::build(&items, options), options)
+}
+
+impl Transcript {
+    /// Render the main conversation as Markdown, including tool output
+    pub fn render_markdown(&self, options: &RenderOptions) -> String {
+        let options = self.render_options(options);
+        markdown(&Document::build(&self.render_items(), &options), &options)
+    }
+
+    /// Render the main conversation as a self-contained HTML page, including tool output
+    pub fn render_html(&self, options: &RenderOptions) -> String {
+        let options = self.render_options(options);
+        html(&Document::build(&self.render_items(), &options), &options)
+    }
+
+    fn render_options(&self, options: &RenderOptions) -> RenderOptions {
+        let mut options = options.clone();
+        if options.title.is_none() {
+            options.title = self.info.summary.clone().or_else(|| self.info.first_prompt.clone());
+        }
+        options
+    }
+
+    /// Items of the main conversation
+    ///
+    /// Tool results are read from the raw entries, since user messages
+    /// cannot carry them.
+    fn render_items(&self) -> Vec<Item> {
+        let mut items = Vec::new();
+        for entry in self.entries.iter().filter(|entry| !entry.is_sidechain) {
+            match &entry.message {
+                Some(Message::User(_)) if entry.is_meta() => {}
+                Some(message) => push_message_items(&mut items, message),
+                None if entry.entry_type() == Some("user") => {
+                    let blocks = entry
+                        .raw
+                        .get("message")
+                        .and_then(|m| m.get("content"))
+                        .and_then(|c| c.as_array());
+                    for block in blocks.into_iter().flatten() {
+                        if block.get("type").and_then(|t| t.as_str()) != Some("tool_result") {
+                            continue;
+                        }
+                        if let Ok(result) = serde_json::from_value::<ToolResultBlock>(block.clone()) {
+                            items.push(Item::ToolResult(result));
+                        }
+                    }
+                }
+                None => {}
+            }
+        }
+        items
+    }
+}
+
+/// A message or content block in rendering order
+enum Item {
+    Prompt(String),
+    Text(String),
+    ToolCall(ToolUseBlock),
+    ToolResult(ToolResultBlock),
+    Model(String),
+    Result(ResultMessage),
+}
+
+fn items_from_messages(messages: &[Message]) -> Vec<Item> {
+    let mut items = Vec::new();
+    for message in messages {
+        push_message_items(&mut items, message);
+    }
+    items
+}
+
+fn push_message_items(items: &mut Vec<Item>, message: &Message) {
+    match message {
+        Message::User(user) => items.push(Item::Prompt(user.content.clone())),
+        Message::Assistant(assistant) => {
+            for block in &assistant.content {
+                items.push(match block {
+                    ContentBlock::Text(text) => Item::Text(text.text.clone()),
+                    ContentBlock::ToolUse(tool) => Item::ToolCall(tool.clone()),
+                    ContentBlock::ToolResult(result) => Item::ToolResult(result.clone()),
+                });
+            }
+        }
+        Message::System(system) => {
+            if let Some(model) = system.data.get("model").and_then(|m| m.as_str()) {
+                items.push(Item::Model(model.to_string()));
+            }
+        }
+        Message::Result(result) => items.push(Item::Result(result.clone())),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    User,
+    Assistant,
+}
+
+/// Content laid out for rendering, shared by the Markdown and HTML writers
+struct Document {
+    sections: Vec<Section>,
+}
+
+enum Section {
+    Turn { role: Role, parts: Vec<Part> },
+    Footer(Vec<String>),
+}
+
+enum Part {
+    Text(String),
+    Tool(ToolCall),
+}
+
+struct ToolCall {
+    name: String,
+    label: Option<String>,
+    input: ToolInput,
+    output: Option<ToolOutput>,
+}
+
+enum ToolInput {
+    Diff(Vec<FileDiff>),
+    Json(String),
+}
+
+struct FileDiff {
+    path: Option<String>,
+    lines: Vec<DiffLine>,
+}
+
+enum DiffLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+struct ToolOutput {
+    text: String,
+    is_error: bool,
+}
+
+impl Document {
+    fn build(items: &[Item], options: &RenderOptions) -> Self {
+        // Results are shown inside the call they answer
+        let mut results: HashMap<&str, &ToolResultBlock> = HashMap::new();
+        for item in items {
+            if let Item::ToolResult(result) = item {
+                results.insert(result.tool_use_id.as_str(), result);
+            }
+        }
+        let call_ids: std::collections::HashSet<&str> = items
+            .iter()
+            .filter_map(|item| match item {
+                Item::ToolCall(tool) => Some(tool.id.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        let mut sections: Vec<Section> = Vec::new();
+        let mut model: Option<&str> = None;
+        for item in items {
+            let (role, part) = match item {
+                Item::Prompt(text) => (Role::User, Part::Text(text.clone())),
+                Item::Text(text) => (Role::Assistant, Part::Text(text.clone())),
+                Item::ToolCall(tool) => (
+                    Role::Assistant,
+                    Part::Tool(tool_call(tool, results.get(tool.id.as_str()).copied(), options)),
+                ),
+                // Results without a matching call still get shown on their own
+                Item::ToolResult(result) if !call_ids.contains(result.tool_use_id.as_str()) => (
+                    Role::User,
+                    Part::Tool(ToolCall {
+                        name: "Tool result".to_string(),
+                        label: Some(result.tool_use_id.clone()),
+                        input: ToolInput::Json(String::new()),
+                        output: Some(tool_output(result, options)),
+                    }),
+                ),
+                Item::ToolResult(_) => continue,
+                Item::Model(name) => {
+                    model = Some(name);
+                    continue;
+                }
+                Item::Result(result) => {
+                    sections.push(Section::Footer(footer_parts(result, model, &options.pricing)));
+                    continue;
+                }
+            };
+            match sections.last_mut() {
+                Some(Section::Turn { role: last, parts }) if *last == role => parts.push(part),
+                _ => sections.push(Section::Turn { role, parts: vec![part] }),
+            }
+        }
+        debug!(items = items.len(), sections = sections.len(), "Laid out conversation for rendering");
+        Self { sections }
+    }
+}
+
+fn tool_call(tool: &ToolUseBlock, result: Option<&ToolResultBlock>, options: &RenderOptions) -> ToolCall {
+    let label = LABEL_FIELDS
+        .iter()
+        .find_map(|field| tool.input.get(*field)?.as_str())
+        .map(|value| {
+            let first_line = value.lines().next().unwrap_or_default();
+            let (label, truncated) = truncate(first_line, MAX_LABEL_CHARS);
+            if truncated || first_line.len() < value.trim_end().len() {
+                format!("{}…", label)
+            } else {
+                label
+            }
+        });
+
+    let input = match tool.name.as_str() {
+        "Edit" => ToolInput::Diff(vec![edit_diff(None, &tool.input)]),
+        "MultiEdit" => {
+            let edits = tool.input.get("edits").and_then(|e| e.as_array());
+            ToolInput::Diff(
+                edits
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|edit| edit.as_object())
+                    .map(|edit| {
+                        let fields: HashMap<String, serde_json::Value> =
+                            edit.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                        edit_diff(fields.get("file_path").and_then(|p| p.as_str()), &fields)
+                    })
+                    .collect(),
+            )
+        }
+        _ => {
+            let json = serde_json::to_string_pretty(&tool.input).unwrap_or_default();
+            ToolInput::Json(truncate_with_note(&json, options.max_tool_input_chars))
+        }
+    };
+
+    ToolCall {
+        name: tool.name.clone(),
+        label,
+        input,
+        output: result.map(|result| tool_output(result, options)),
+    }
+}
+
+fn edit_diff(path: Option<&str>, input: &HashMap<String, serde_json::Value>) -> FileDiff {
+    let field = |key: &str| input.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+    FileDiff {
+        path: path.map(str::to_string),
+        lines: line_diff(field("old_string"), field("new_string")),
+    }
+}
+
+/// Lines removed and added between `old` and `new`, keeping the lines they share at either end as context
+fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let context = |lines: &[&str]| lines.iter().map(|l| DiffLine::Context(l.to_string())).collect::<Vec<_>>();
+    let mut lines = context(&old[..prefix]);
+    lines.extend(old[prefix..old.len() - suffix].iter().map(|l| DiffLine::Removed(l.to_string())));
+    lines.extend(new[prefix..new.len() - suffix].iter().map(|l| DiffLine::Added(l.to_string())));
+    lines.extend(context(&old[old.len() - suffix..]));
+    lines
+}
+
+fn tool_output(result: &ToolResultBlock, options: &RenderOptions) -> ToolOutput {
+    let text = match &result.content {
+        None => String::new(),
+        Some(ToolResultContent::Text(text)) => text.clone(),
+        Some(ToolResultContent::Structured(blocks)) => blocks
+            .iter()
+            .map(|block| match block.get("text").and_then(|t| t.as_str()) {
+                Some(text) => text.to_string(),
+                None => format!(
+                    "[{} content]",
+                    block.get("type").and_then(|t| t.as_str()).unwrap_or("unknown")
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    ToolOutput {
+        text: truncate_with_note(&text, options.max_tool_result_chars),
+        is_error: result.is_error.unwrap_or(false),
+    }
+}
+
+fn footer_parts(result: &ResultMessage, model: Option<&str>, pricing: &PricingTable) -> Vec<String> {
+    let mut parts = vec![result.subtype.clone()];
+    if let Some(model) = result.model.as_deref().or(model) {
+        parts.push(model.to_string());
+    }
+    parts.push(format!(
+        "{} turn{}",
+        result.num_turns,
+        if result.num_turns == 1 { "" } else { "s" }
+    ));
+    parts.push(format!("{:.1}s", result.duration_ms as f64 / 1000.0));
+
+    let cost = result.total_cost_usd.or(result.estimated_cost_usd).or_else(|| {
+        let model = result.model.as_deref().or(model)?;
+        pricing.cost(model, &result.typed_usage()?)
+    });
+    if let Some(cost) = cost {
+        parts.push(format!("${:.4}", cost));
+    }
+    if let Some(usage) = result.typed_usage() {
+        parts.push(format!("{} input tokens", usage.input_tokens));
+        parts.push(format!("{} output tokens", usage.output_tokens));
+        if usage.cache_read_input_tokens > 0 {
+            parts.push(format!("{} cache read tokens", usage.cache_read_input_tokens));
+        }
+        if usage.cache_creation_input_tokens > 0 {
+            parts.push(format!("{} cache write tokens", usage.cache_creation_input_tokens));
+        }
+    }
+    parts
+}
+
+/// The first `max_chars` characters of `text`, and whether anything was cut
+fn truncate(text: &str, max_chars: usize) -> (String, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => (text[..end].to_string(), true),
+        None => (text.to_string(), false),
+    }
+}
+
+fn truncate_with_note(text: &str, max_chars: usize) -> String {
+    match truncate(text, max_chars) {
+        (kept, true) => {
+            let omitted = text.chars().count() - max_chars;
+            format!("{}\n… {} more characters truncated", kept, omitted)
+        }
+        (kept, false) => kept,
+    }
+}
+
+fn markdown(document: &Document, options: &RenderOptions) -> String {
+    let mut out = String::new();
+    if let Some(title) = &options.title {
+        let _ = writeln!(out, "# {}\n", title.lines().next().unwrap_or_default());
+    }
+
+    for section in &document.sections {
+        match section {
+            Section::Turn { role, parts } => {
+                let heading = match role {
+                    Role::User => "User",
+                    Role::Assistant => "Assistant",
+                };
+                let _ = writeln!(out, "## {}\n", heading);
+                for part in parts {
+                    match part {
+                        Part::Text(text) => {
+                            let _ = writeln!(out, "{}\n", text.trim_end());
+                        }
+                        Part::Tool(tool) => markdown_tool(&mut out, tool),
+                    }
+                }
+            }
+            Section::Footer(parts) => {
+                let _ = writeln!(out, "---\n\n**Result:** {}\n", parts.join(" · "));
+            }
+        }
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+fn markdown_tool(out: &mut String, tool: &ToolCall) {
+    let _ = write!(out, "<details>\n<summary><code>{}</code>", escape_html(&tool.name));
+    if let Some(label) = &tool.label {
+        let _ = write!(out, " {}", escape_html(label));
+    }
+    if tool.output.as_ref().is_some_and(|output| output.is_error) {
+        out.push_str(" (error)");
+    }
+    out.push_str("</summary>\n\n");
+
+    match &tool.input {
+        ToolInput::Diff(diffs) => {
+            for diff in diffs {
+                if let Some(path) = &diff.path {
+                    let _ = writeln!(out, "`{}`\n", path);
+                }
+                let body: String = diff
+                    .lines
+                    .iter()
+                    .map(|line| match line {
+                        DiffLine::Context(l) => format!(" {}\n", l),
+                        DiffLine::Removed(l) => format!("-{}\n", l),
+                        DiffLine::Added(l) => format!("+{}\n", l),
+                    })
+                    .collect();
+                out.push_str(&code_block(&body, "diff"));
+            }
+        }
+        ToolInput::Json(json) if json.is_empty() => {}
+        ToolInput::Json(json) => out.push_str(&code_block(json, "json")),
+    }
+
+    if let Some(output) = &tool.output {
+        let _ = writeln!(out, "**{}**\n", if output.is_error { "Error" } else { "Output" });
+        out.push_str(&code_block(&output.text, ""));
+    }
+    out.push_str("</details>\n\n");
+}
+
+/// A fenced code block whose fence is longer than any backtick run in `body`
+fn code_block(body: &str, language: &str) -> String {
+    let longest_run = body
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    format!("{}{}\n{}\n{}\n\n", fence, language, body.trim_end_matches('\n'), fence)
+}
+
+fn html(document: &Document, options: &RenderOptions) -> String {
+    let title = options
+        .title
+        .as_deref()
+        .and_then(|title| title.lines().next())
+        .unwrap_or("Claude Code transcript");
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>\n{HTML_STYLE}</style>\n</head>\n<body>\n<main>\n<h1>{title}</h1>\n",
+        title = escape_html(title)
+    );
+
+    for section in &document.sections {
+        match section {
+            Section::Turn { role, parts } => {
+                let (class, heading) = match role {
+                    Role::User => ("user", "User"),
+                    Role::Assistant => ("assistant", "Assistant"),
+                };
+                let _ = writeln!(out, "<section class=\"{}\">\n<h2>{}</h2>", class, heading);
+                for part in parts {
+                    match part {
+                        Part::Text(text) => {
+                            let _ = writeln!(out, "<div class=\"text\">{}</div>", escape_html(text.trim_end()));
+                        }
+                        Part::Tool(tool) => html_tool(&mut out, tool),
+                    }
+                }
+                out.push_str("</section>\n");
+            }
+            Section::Footer(parts) => {
+                let _ = writeln!(out, "<footer><strong>Result:</strong> {}</footer>", escape_html(&parts.join(" · ")));
+            }
+        }
+    }
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
+
+fn html_tool(out: &mut String, tool: &ToolCall) {
+    let is_error = tool.output.as_ref().is_some_and(|output| output.is_error);
+    let _ = write!(
+        out,
+        "<details class=\"tool{}\">\n<summary><code>{}</code>",
+        if is_error { " error" } else { "" },
+        escape_html(&tool.name)
+    );
+    if let Some(label) = &tool.label {
+        let _ = write!(out, " {}", escape_html(label));
+    }
+    if is_error {
+        out.push_str(" (error)");
+    }
+    out.push_str("</summary>\n<div>\n");
+
+    match &tool.input {
+        ToolInput::Diff(diffs) => {
+            for diff in diffs {
+                if let Some(path) = &diff.path {
+                    let _ = writeln!(out, "<div class=\"label\">{}</div>", escape_html(path));
+                }
+                out.push_str("<pre class=\"diff\">");
+                for line in &diff.lines {
+                    let _ = match line {
+                        DiffLine::Context(l) => write!(out, "<span> {}</span>", escape_html(l)),
+                        DiffLine::Removed(l) => write!(out, "<span class=\"del\">-{}</span>", escape_html(l)),
+                        DiffLine::Added(l) => write!(out, "<span class=\"add\">+{}</span>", escape_html(l)),
+                    };
+                }
+                out.push_str("</pre>\n");
+            }
+        }
+        ToolInput::Json(json) if json.is_empty() => {}
+        ToolInput::Json(json) => {
+            let _ = writeln!(out, "<div class=\"label\">Input</div>\n<pre>{}</pre>", escape_html(json));
+        }
+    }
+
+    if let Some(output) = &tool.output {
+        let _ = writeln!(
+            out,
+            "<div class=\"label\">{}</div>\n<pre{}>{}</pre>",
+            if output.is_error { "Error" } else { "Output" },
+            if output.is_error { " class=\"error\"" } else { "" },
+            escape_html(&output.text)
+        );
+    }
+    out.push_str("</div>\n</details>\n");
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,145 @@
+//! Retry policy for transient query failures
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ClaudeSDKError;
+
+/// Kinds of failure a [`RetryPolicy`] may retry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryOn {
+    /// The API reported it was overloaded
+    Overloaded,
+    /// The API rate limited the request
+    RateLimited,
+    /// Any other execution failure, including CLI connection and stream errors
+    Execution,
+}
+
+/// Retry policy for re-launching the CLI after transient failures
+///
+/// When a run ends with a retryable error, the CLI is started again after a
+/// backoff delay. If the failed run already created a session, the retry
+/// resumes it with [`RetryPolicy::resume_prompt`] instead of starting over.
+///
+/// Durations serialize as seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: u32,
+
+    /// Delay before the first retry
+    #[serde(with = "duration_secs")]
+    pub initial_backoff: Duration,
+
+    /// Upper bound for the delay between attempts
+    #[serde(with = "duration_secs")]
+    pub max_backoff: Duration,
+
+    /// Factor the delay grows by after each failed attempt
+    pub backoff_multiplier: f64,
+
+    /// Random variation applied to each delay, as a fraction (0.0 - 1.0)
+    pub jitter: f64,
+
+    /// Failure kinds that trigger a retry
+    pub retry_on: Vec<RetryOn>,
+
+    /// Prompt sent when resuming a session that failed part way through
+    pub resume_prompt: String,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            backoff_multiplier: 2.0,
+            jitter: 0.2,
+            retry_on: vec![RetryOn::Overloaded, RetryOn::RateLimited],
+            resume_prompt: "Continue from where you left off.".to_string(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            retry_on: Vec::new(),
+            ..Self::default()
+        }
+    }
+
+    /// Check whether an error should be retried under this policy
+    pub fn is_retryable(&self, error: &ClaudeSDKError) -> bool {
+        let kind = match error {
+            ClaudeSDKError::Overloaded(_) => RetryOn::Overloaded,
+            ClaudeSDKError::RateLimited(_) => RetryOn::RateLimited,
+            ClaudeSDKError::Execution(_)
+            | ClaudeSDKError::CLIConnection(_)
+            | ClaudeSDKError::Process(_)
+            | ClaudeSDKError::Io(_) => RetryOn::Execution,
+            _ => return false,
+        };
+        self.retry_on.contains(&kind)
+    }
+
+    /// Delay before the next attempt, after `failed_attempts` failures
+    ///
+    /// A `retry_after` hint from the API is honoured when it is longer than
+    /// the computed backoff.
+    pub fn backoff(&self, failed_attempts: u32, retry_after: Option<Duration>) -> Duration {
+        let exponent = failed_attempts.saturating_sub(1).min(32) as i32;
+        let base = self.initial_backoff.as_secs_f64() * self.backoff_multiplier.powi(exponent);
+        let capped = base.min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let jittered = (capped * (1.0 + jitter * (2.0 * unit_random() - 1.0))).max(0.0);
+        // Out-of-range values, e.g. from a huge max_backoff, fall back to the cap
+        let delay = Duration::try_from_secs_f64(jittered).unwrap_or(self.max_backoff);
+        match retry_after {
+            Some(retry_after) if retry_after > delay => retry_after,
+            _ => delay,
+        }
+    }
+}
+
+/// Cheap pseudo-random value in `[0, 1)`, good enough to spread out retries
+fn unit_random() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    // xorshift to decorrelate nearby timestamps
+    let mut x = nanos as u64 | 1;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    (x % 1_000_000) as f64 / 1_000_000.0
+}
+
+/// Serde format for durations as (fractional) seconds
+mod duration_secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let secs = f64::deserialize(deserializer)?;
+        Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,383 @@
+//! Exporting sessions to portable archives and importing them elsewhere
+//!
+//! A bundle is a gzipped tar archive holding `manifest.json`, the session's
+//! `transcript.jsonl`, and the files the CLI keeps in the session's side
+//! directory under `files/`.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use super::{is_plain_relative, SessionStore, Transcript};
+use crate::errors::{ClaudeSDKError, ConfigError};
+use crate::types::{ClaudeCodeOptions, PermissionMode};
+
+/// Version of the bundle layout written by this SDK
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_PATH: &str = "manifest.json";
+const TRANSCRIPT_PATH: &str = "transcript.jsonl";
+const FILES_DIR: &str = "files";
+
+/// Description of a bundled session, stored as `manifest.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub session_id: String,
+    /// Working directory the session ran in on the exporting machine
+    pub cwd: Option<PathBuf>,
+    pub exported_at: DateTime<Utc>,
+    #[serde(default)]
+    pub first_prompt: Option<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub git_branch: Option<String>,
+    #[serde(default)]
+    pub message_count: usize,
+    #[serde(default)]
+    pub last_cost_usd: Option<f64>,
+    /// Query options the session was run with, if the exporter provided them
+    #[serde(default)]
+    pub options: Option<BundleOptions>,
+}
+
+/// The query options recorded in a bundle
+///
+/// Only options that shape the conversation are kept; machine-specific
+/// settings such as the working directory are not.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BundleOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_models: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub append_system_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_tools: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disallowed_tools: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_mode: Option<PermissionMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_thinking_tokens: Option<u32>,
+}
+
+impl From<&ClaudeCodeOptions> for BundleOptions {
+    fn from(options: &ClaudeCodeOptions) -> Self {
+        Self {
+            model: options.model.clone(),
+            fallback_models: options.fallback_models.clone(),
+            system_prompt: options.system_prompt.clone(),
+            append_system_prompt: options.append_system_prompt.clone(),
+            allowed_tools: options.allowed_tools.clone(),
+            disallowed_tools: options.disallowed_tools.clone(),
+            permission_mode: options.permission_mode.clone(),
+            max_turns: options.max_turns,
+            max_thinking_tokens: options.max_thinking_tokens,
+        }
+    }
+}
+
+impl BundleOptions {
+    /// Apply the recorded options on top of `options`
+    pub fn apply_to(&self, options: &mut ClaudeCodeOptions) {
+        if self.model.is_some() {
+            options.model = self.model.clone();
+        }
+        if !self.fallback_models.is_empty() {
+            options.fallback_models = self.fallback_models.clone();
+        }
+        if self.system_prompt.is_some() {
+            options.system_prompt = self.system_prompt.clone();
+        }
+        if self.append_system_prompt.is_some() {
+            options.append_system_prompt = self.append_system_prompt.clone();
+        }
+        if !self.allowed_tools.is_empty() {
+            options.allowed_tools = self.allowed_tools.clone();
+        }
+        if !self.disallowed_tools.is_empty() {
+            options.disallowed_tools = self.disallowed_tools.clone();
+        }
+        if self.permission_mode.is_some() {
+            options.permission_mode = self.permission_mode.clone();
+        }
+        if self.max_turns.is_some() {
+            options.max_turns = self.max_turns;
+        }
+        if self.max_thinking_tokens.is_some() {
+            options.max_thinking_tokens = self.max_thinking_tokens;
+        }
+    }
+}
+
+/// A session imported from a bundle
+#[derive(Debug, Clone)]
+pub struct ImportedSession {
+    pub manifest: BundleManifest,
+    /// Where the transcript was written
+    pub transcript_path: PathBuf,
+    /// Options that resume the session in the target directory
+    pub options: ClaudeCodeOptions,
+}
+
+impl SessionStore {
+    /// Write a session into a bundle at `archive`
+    ///
+    /// Pass the options the session was run with to record them in the bundle.
+    pub fn export_session(
+        &self,
+        session_id: &str,
+        archive: impl AsRef<Path>,
+        options: Option<&ClaudeCodeOptions>,
+    ) -> Result<BundleManifest, ClaudeSDKError> {
+        let archive = archive.as_ref();
+        let transcript_path = self.find_session(session_id)?.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, format!("Session not found: {}", session_id))
+        })?;
+        let info = Transcript::read(&transcript_path)?.info;
+
+        let manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            session_id: info.session_id,
+            cwd: info.cwd,
+            exported_at: Utc::now(),
+            first_prompt: info.first_prompt,
+            summary: info.summary,
+            git_branch: info.git_branch,
+            message_count: info.message_count,
+            last_cost_usd: info.last_cost_usd,
+            options: options.map(BundleOptions::from),
+        };
+        let manifest_json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| ConfigError::with_path(format!("Failed to serialize bundle manifest: {}", e), archive))?;
+
+        let mut builder = tar::Builder::new(GzEncoder::new(File::create(archive)?, Compression::default()));
+        append_bytes(&mut builder, MANIFEST_PATH, &manifest_json)?;
+        builder.append_path_with_name(&transcript_path, TRANSCRIPT_PATH)?;
+        let side_dir = transcript_path.with_extension("");
+        if side_dir.is_dir() {
+            builder.append_dir_all(FILES_DIR, &side_dir)?;
+        }
+        builder.into_inner()?.finish()?;
+
+        info!(session_id, archive = %archive.display(), "Exported session bundle");
+        Ok(manifest)
+    }
+
+    /// Import a bundle as a session of the project at `target_cwd`
+    ///
+    /// Every occurrence of the original working directory in the transcript
+    /// is rewritten to `target_cwd`, so the session resumes from there. The
+    /// session keeps its id; importing over an existing session fails.
+    pub fn import_session(
+        &self,
+        archive: impl AsRef<Path>,
+        target_cwd: &Path,
+    ) -> Result<ImportedSession, ClaudeSDKError> {
+        let archive = archive.as_ref();
+        let mut manifest = None;
+        let mut transcript = None;
+        let mut files = Vec::new();
+
+        let mut entries = tar::Archive::new(GzDecoder::new(File::open(archive)?));
+        for entry in entries.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            if path == Path::new(MANIFEST_PATH) {
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents)?;
+                manifest = Some(parse_manifest(&contents, archive)?);
+            } else if path == Path::new(TRANSCRIPT_PATH) {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                transcript = Some(contents);
+            } else if let Ok(relative) = path.strip_prefix(FILES_DIR) {
+                if entry.header().entry_type().is_file() && is_plain_relative(relative) {
+                    let mut contents = Vec::new();
+                    entry.read_to_end(&mut contents)?;
+                    files.push((relative.to_path_buf(), contents));
+                }
+            } else {
+                debug!(path = %path.display(), "Ignoring unknown bundle entry");
+            }
+        }
+
+        let manifest = manifest.ok_or_else(|| ConfigError::with_path("Bundle has no manifest", archive))?;
+        let transcript = transcript.ok_or_else(|| ConfigError::with_path("Bundle has no transcript", archive))?;
+        if manifest.format_version > BUNDLE_FORMAT_VERSION {
+            return Err(ConfigError::with_path(
+                format!("Unsupported bundle format version {}", manifest.format_version),
+                archive,
+            )
+            .into());
+        }
+        let session_path = Path::new(&manifest.session_id);
+        if !is_plain_relative(session_path) || session_path.components().count() != 1 {
+            return Err(ConfigError::with_path("Bundle has an invalid session id", archive).into());
+        }
+
+        let transcript_path = self.session_path(target_cwd, &manifest.session_id)?;
+        if transcript_path.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("Session already exists: {}", transcript_path.display()),
+            )
+            .into());
+        }
+
+        let transcript = match &manifest.cwd {
+            Some(original) => rewrite_transcript_paths(&transcript, original, target_cwd),
+            None => {
+                warn!(session_id = %manifest.session_id, "Bundle does not record a working directory; paths left as is");
+                transcript
+            }
+        };
+        std::fs::create_dir_all(self.project_dir(target_cwd))?;
+        std::fs::write(&transcript_path, transcript)?;
+
+        let side_dir = transcript_path.with_extension("");
+        for (relative, contents) in files {
+            let path = side_dir.join(relative);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, contents)?;
+        }
+
+        let mut options = ClaudeCodeOptions::default();
+        if let Some(bundle_options) = &manifest.options {
+            bundle_options.apply_to(&mut options);
+        }
+        options.cwd = Some(target_cwd.to_path_buf());
+        options.resume = Some(manifest.session_id.clone());
+
+        info!(
+            session_id = %manifest.session_id,
+            path = %transcript_path.display(),
+            "Imported session bundle"
+        );
+        Ok(ImportedSession {
+            manifest,
+            transcript_path,
+            options,
+        })
+    }
+}
+
+/// Read only the manifest of a bundle
+pub fn read_bundle_manifest(archive: impl AsRef<Path>) -> Result<BundleManifest, ClaudeSDKError> {
+    let archive = archive.as_ref();
+    let mut entries = tar::Archive::new(GzDecoder::new(File::open(archive)?));
+    for entry in entries.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_ref() == Path::new(MANIFEST_PATH) {
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            return parse_manifest(&contents, archive);
+        }
+    }
+    Err(ConfigError::with_path("Bundle has no manifest", archive).into())
+}
+
+/// Parse a manifest, rejecting working directories that cannot be rewritten safely
+fn parse_manifest(contents: &[u8], archive: &Path) -> Result<BundleManifest, ClaudeSDKError> {
+    let manifest: BundleManifest = serde_json::from_slice(contents)
+        .map_err(|e| ConfigError::with_path(format!("Invalid bundle manifest: {}", e), archive))?;
+    if let Some(cwd) = &manifest.cwd {
+        // Every transcript string under cwd is rewritten, so it has to name a real project
+        if !cwd.is_absolute() || cwd.parent().is_none() {
+            return Err(ConfigError::with_path(
+                format!("Bundle manifest has an invalid working directory {:?}", cwd),
+                archive,
+            )
+            .into());
+        }
+    }
+    Ok(manifest)
+}
+
+fn append_bytes<W: std::io::Write>(builder: &mut tar::Builder<W>, path: &str, contents: &[u8]) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, path, contents)
+}
+
+/// Rewrite `original` to `target` in every string of every transcript line
+fn rewrite_transcript_paths(transcript: &str, original: &Path, target: &Path) -> String {
+    let original = original.to_string_lossy();
+    let target = target.to_string_lossy();
+    let mut rewritten = String::with_capacity(transcript.len());
+    for line in transcript.lines() {
+        match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(mut value) => {
+                rewrite_strings(&mut value, &original, &target);
+                rewritten.push_str(&value.to_string());
+            }
+            Err(_) => rewritten.push_str(line),
+        }
+        rewritten.push('\n');
+    }
+    rewritten
+}
+
+fn rewrite_strings(value: &mut serde_json::Value, original: &str, target: &str) {
+    match value {
+        serde_json::Value::String(s) => {
+            if let Some(rewritten) = rewrite_path_prefix(s, original, target) {
+                *s = rewritten;
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|v| rewrite_strings(v, original, target)),
+        serde_json::Value::Object(map) => map.values_mut().for_each(|v| rewrite_strings(v, original, target)),
+        _ => {}
+    }
+}
+
+/// Replace `original` where it appears as a whole path, not as part of a longer one
+///
+/// A match must start the string or follow a character that cannot be part
+/// of a path, and must be followed by the end of the string, a `/` or such a
+/// character.
+fn rewrite_path_prefix(s: &str, original: &str, target: &str) -> Option<String> {
+    if original.is_empty() || !s.contains(original) {
+        return None;
+    }
+    let mut result = String::with_capacity(s.len());
+    let mut copied = 0;
+    for (index, _) in s.match_indices(original) {
+        let end = index + original.len();
+        let starts_path = s[..index].chars().next_back().is_none_or(|c| !is_path_char(c));
+        let ends_path = s[end..].chars().next().is_none_or(|c| c == '/' || !is_path_char(c));
+        if starts_path && ends_path {
+            result.push_str(&s[copied..index]);
+            result.push_str(target);
+            copied = end;
+        }
+    }
+    if copied == 0 {
+        return None;
+    }
+    result.push_str(&s[copied..]);
+    Some(result)
+}
+
+fn is_path_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/' | '\\' | '~')
+}
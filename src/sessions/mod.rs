@@ -0,0 +1,431 @@
+//! Access to the session transcripts the CLI keeps on disk
+//!
+//! The CLI stores each session as a JSONL transcript at
+//! `<config dir>/projects/<encoded cwd>/<session id>.jsonl`, where the config
+//! dir is `$CLAUDE_CONFIG_DIR` or `~/.claude`, and the working directory is
+//! encoded by replacing every character other than an ASCII letter or digit
+//! with `-`.
+
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use tracing::{debug, info, warn};
+
+use crate::client::InternalClient;
+use crate::errors::{ClaudeSDKError, ConfigError};
+use crate::pricing::{PricingTable, Usage};
+use crate::types::*;
+
+mod bundle;
+mod retention;
+mod search;
+
+pub use bundle::{read_bundle_manifest, BundleManifest, BundleOptions, ImportedSession, BUNDLE_FORMAT_VERSION};
+pub use retention::{DiskUsage, PruneReport, PrunedSession, RetentionPolicy};
+pub use search::{IndexUpdate, Passage, PassageKind, SearchHit, SearchIndex, Snippet};
+
+/// Environment variable the CLI reads its config directory from
+pub const CONFIG_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// The CLI's config directory: `$CLAUDE_CONFIG_DIR`, or `~/.claude`
+pub fn default_config_dir() -> Option<PathBuf> {
+    match std::env::var_os(CONFIG_DIR_ENV) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => home::home_dir().map(|home| home.join(".claude")),
+    }
+}
+
+/// Name of the directory the CLI keeps a project's sessions in
+pub fn encode_project_dir(cwd: &Path) -> String {
+    cwd.to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Summary of one stored session
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionInfo {
+    pub session_id: String,
+    /// Path of the transcript file
+    pub path: PathBuf,
+    /// Working directory the session ran in, as recorded in the transcript
+    pub cwd: Option<PathBuf>,
+    /// First prompt the user typed
+    pub first_prompt: Option<String>,
+    /// Latest summary the CLI wrote for the session
+    pub summary: Option<String>,
+    pub git_branch: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Number of user and assistant messages in the main conversation
+    pub message_count: usize,
+    /// Cost the CLI last recorded for the session, or else an estimate from
+    /// the usage on its assistant messages
+    pub last_cost_usd: Option<f64>,
+    /// Size of the transcript file in bytes
+    pub size_bytes: u64,
+}
+
+/// One line of a transcript
+#[derive(Debug, Clone)]
+pub struct TranscriptEntry {
+    /// The line as stored
+    pub raw: serde_json::Value,
+    /// Typed form, for the message types the SDK models
+    ///
+    /// Tool results the CLI fed back to the model are kept only in `raw`.
+    pub message: Option<Message>,
+    pub uuid: Option<String>,
+    pub parent_uuid: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Whether the entry belongs to a subagent rather than the main conversation
+    pub is_sidechain: bool,
+}
+
+impl TranscriptEntry {
+    fn from_value(raw: serde_json::Value) -> Self {
+        let str_field = |key: &str| raw.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let uuid = str_field("uuid");
+        let parent_uuid = str_field("parentUuid");
+        let timestamp = raw
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.with_timezone(&Utc));
+        let is_sidechain = raw.get("isSidechain").and_then(|v| v.as_bool()).unwrap_or(false);
+        let message = parse_transcript_message(&raw);
+        Self {
+            raw,
+            message,
+            uuid,
+            parent_uuid,
+            timestamp,
+            is_sidechain,
+        }
+    }
+
+    /// Entry type, e.g. `user`, `assistant` or `summary`
+    pub fn entry_type(&self) -> Option<&str> {
+        self.raw.get("type").and_then(|v| v.as_str())
+    }
+
+    /// Whether the CLI injected this entry rather than the user typing it
+    pub fn is_meta(&self) -> bool {
+        self.raw.get("isMeta").and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+}
+
+/// Typed form of a transcript line
+///
+/// Transcripts store user turns with block content, so text blocks are
+/// joined into a [`UserMessage`]; everything else goes through the same
+/// parser as live CLI output.
+fn parse_transcript_message(raw: &serde_json::Value) -> Option<Message> {
+    let data: HashMap<String, serde_json::Value> = raw.as_object()?.clone().into_iter().collect();
+    if let Some(message) = InternalClient::parse_message(&data) {
+        return Some(message);
+    }
+    if raw.get("type")?.as_str()? != "user" {
+        return None;
+    }
+    let blocks = raw.get("message")?.get("content")?.as_array()?;
+    let text: Vec<&str> = blocks
+        .iter()
+        .filter(|block| block.get("type").and_then(|v| v.as_str()) == Some("text"))
+        .filter_map(|block| block.get("text").and_then(|v| v.as_str()))
+        .collect();
+    if text.is_empty() {
+        return None;
+    }
+    Some(Message::User(UserMessage {
+        content: text.join("\n"),
+    }))
+}
+
+/// A parsed session transcript
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub info: SessionInfo,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    /// Read and parse a transcript file, skipping lines that are not JSON
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, ClaudeSDKError> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path)?;
+        let size_bytes = file.metadata()?.len();
+
+        let mut entries = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(value) => entries.push(TranscriptEntry::from_value(value)),
+                Err(e) => warn!(path = %path.display(), line = index + 1, error = %e, "Skipping malformed transcript line"),
+            }
+        }
+
+        let session_id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Not a transcript file"))?;
+        let info = summarize(session_id, path, size_bytes, &entries);
+        debug!(
+            path = %path.display(),
+            entries = entries.len(),
+            message_count = info.message_count,
+            "Read session transcript"
+        );
+        Ok(Self { info, entries })
+    }
+
+    /// Messages of the main conversation, in order
+    pub fn messages(&self) -> Vec<Message> {
+        self.entries
+            .iter()
+            .filter(|entry| !entry.is_sidechain)
+            .filter_map(|entry| entry.message.clone())
+            .collect()
+    }
+}
+
+fn summarize(session_id: String, path: &Path, size_bytes: u64, entries: &[TranscriptEntry]) -> SessionInfo {
+    let pricing = PricingTable::default();
+    let mut info = SessionInfo {
+        session_id,
+        path: path.to_path_buf(),
+        cwd: None,
+        first_prompt: None,
+        summary: None,
+        git_branch: None,
+        started_at: None,
+        updated_at: None,
+        message_count: 0,
+        last_cost_usd: None,
+        size_bytes,
+    };
+    let mut reported_cost = None;
+    let mut estimated_cost = 0.0;
+    let mut seen_message_ids = HashSet::new();
+    let mut unpriced_models = HashSet::new();
+
+    for entry in entries {
+        let str_field = |key: &str| entry.raw.get(key).and_then(|v| v.as_str());
+        if let Some(cwd) = str_field("cwd") {
+            info.cwd.get_or_insert_with(|| PathBuf::from(cwd));
+        }
+        if let Some(branch) = str_field("gitBranch") {
+            info.git_branch = Some(branch.to_string());
+        }
+        if let Some(timestamp) = entry.timestamp {
+            info.started_at = Some(info.started_at.map_or(timestamp, |t| t.min(timestamp)));
+            info.updated_at = Some(info.updated_at.map_or(timestamp, |t| t.max(timestamp)));
+        }
+        if let Some(cost) = entry.raw.get("totalCostUSD").and_then(|v| v.as_f64()) {
+            reported_cost = Some(cost);
+        }
+        match entry.entry_type() {
+            Some("summary") => {
+                if let Some(summary) = str_field("summary") {
+                    info.summary = Some(summary.to_string());
+                }
+            }
+            Some("assistant") => {
+                let message = entry.raw.get("message");
+                let id = message.and_then(|m| m.get("id")).and_then(|v| v.as_str());
+                // Each content block of a response is stored with the same usage
+                if id.is_none_or(|id| seen_message_ids.insert(id.to_string())) {
+                    let model = message.and_then(|m| m.get("model")).and_then(|v| v.as_str());
+                    let usage = message.and_then(|m| m.get("usage")).and_then(Usage::from_value);
+                    if let (Some(model), Some(usage)) = (model, usage) {
+                        match pricing.cost(model, &usage) {
+                            Some(cost) => estimated_cost += cost,
+                            None if unpriced_models.insert(model) => {
+                                warn!(model, path = %path.display(), "No pricing for model, session cost estimate leaves out its usage");
+                            }
+                            None => {}
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if entry.is_sidechain {
+            continue;
+        }
+        match &entry.message {
+            Some(Message::User(user)) => {
+                info.message_count += 1;
+                if info.first_prompt.is_none() && !entry.is_meta() {
+                    info.first_prompt = Some(user.content.clone());
+                }
+            }
+            Some(Message::Assistant(_)) => info.message_count += 1,
+            _ => {}
+        }
+    }
+
+    info.last_cost_usd = reported_cost.or((estimated_cost > 0.0).then_some(estimated_cost));
+    info
+}
+
+/// Sessions stored under a CLI config directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStore {
+    config_dir: PathBuf,
+}
+
+impl SessionStore {
+    /// Use the sessions under `config_dir`
+    pub fn new(config_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            config_dir: config_dir.into(),
+        }
+    }
+
+    /// Use the sessions under the CLI's default config directory
+    pub fn open_default() -> Result<Self, ClaudeSDKError> {
+        let config_dir = default_config_dir()
+            .ok_or_else(|| ConfigError::new("Cannot locate the Claude config directory: no home directory"))?;
+        info!(config_dir = %config_dir.display(), "Using Claude config directory");
+        Ok(Self::new(config_dir))
+    }
+
+    /// The config directory this store reads
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    /// Directory holding one subdirectory per project
+    pub fn projects_dir(&self) -> PathBuf {
+        self.config_dir.join("projects")
+    }
+
+    /// Directory holding the sessions of the project at `cwd`
+    pub fn project_dir(&self, cwd: &Path) -> PathBuf {
+        self.projects_dir().join(encode_project_dir(cwd))
+    }
+
+    /// Transcript path of a session of the project at `cwd`
+    ///
+    /// Fails for ids that would point outside the project directory.
+    pub fn session_path(&self, cwd: &Path, session_id: &str) -> Result<PathBuf, ClaudeSDKError> {
+        check_session_id(session_id)?;
+        Ok(self.project_dir(cwd).join(format!("{}.jsonl", session_id)))
+    }
+
+    /// Every project directory that holds sessions
+    pub fn project_dirs(&self) -> Result<Vec<PathBuf>, ClaudeSDKError> {
+        let mut dirs = Vec::new();
+        for entry in read_dir_if_exists(&self.projects_dir())? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                dirs.push(entry.path());
+            }
+        }
+        dirs.sort();
+        Ok(dirs)
+    }
+
+    /// Transcript files in a project directory
+    pub fn transcript_paths(project_dir: &Path) -> Result<Vec<PathBuf>, ClaudeSDKError> {
+        let mut paths = Vec::new();
+        for entry in read_dir_if_exists(project_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "jsonl") && path.is_file() {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Sessions of the project at `cwd`, most recently updated first
+    pub fn list_sessions(&self, cwd: &Path) -> Result<Vec<SessionInfo>, ClaudeSDKError> {
+        Self::list_sessions_in(&self.project_dir(cwd))
+    }
+
+    /// Sessions of every project, most recently updated first
+    pub fn list_all_sessions(&self) -> Result<Vec<SessionInfo>, ClaudeSDKError> {
+        let mut sessions = Vec::new();
+        for project_dir in self.project_dirs()? {
+            sessions.extend(Self::list_sessions_in(&project_dir)?);
+        }
+        sort_by_recency(&mut sessions);
+        Ok(sessions)
+    }
+
+    /// Sessions stored in one project directory, most recently updated first
+    pub fn list_sessions_in(project_dir: &Path) -> Result<Vec<SessionInfo>, ClaudeSDKError> {
+        let mut sessions = Vec::new();
+        for path in Self::transcript_paths(project_dir)? {
+            match Transcript::read(&path) {
+                Ok(transcript) => sessions.push(transcript.info),
+                Err(e) => warn!(path = %path.display(), error = %e, "Skipping unreadable transcript"),
+            }
+        }
+        sort_by_recency(&mut sessions);
+        debug!(project_dir = %project_dir.display(), sessions = sessions.len(), "Listed sessions");
+        Ok(sessions)
+    }
+
+    /// Transcript path of a session in any project
+    ///
+    /// Fails for ids that would point outside the projects directory.
+    pub fn find_session(&self, session_id: &str) -> Result<Option<PathBuf>, ClaudeSDKError> {
+        check_session_id(session_id)?;
+        let file_name = format!("{}.jsonl", session_id);
+        for project_dir in self.project_dirs()? {
+            let path = project_dir.join(&file_name);
+            if path.is_file() {
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Read a session's transcript, looking for it in every project
+    pub fn read_session(&self, session_id: &str) -> Result<Transcript, ClaudeSDKError> {
+        let path = self
+            .find_session(session_id)?
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, format!("Session not found: {}", session_id)))?;
+        Transcript::read(path)
+    }
+}
+
+/// Reject ids that are not a single plain file name, such as `../x` or `/tmp/x`
+fn check_session_id(session_id: &str) -> Result<(), ClaudeSDKError> {
+    let path = Path::new(session_id);
+    if session_id.is_empty() || session_id.contains(['/', '\\']) || !is_plain_relative(path) || path.components().count() != 1 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid session id: {:?}", session_id),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Whether a path stays inside the directory it is joined onto
+pub(crate) fn is_plain_relative(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
+fn read_dir_if_exists(dir: &Path) -> Result<Vec<std::io::Result<std::fs::DirEntry>>, ClaudeSDKError> {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => Ok(entries.collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn sort_by_recency(sessions: &mut [SessionInfo]) {
+    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| a.session_id.cmp(&b.session_id)));
+}
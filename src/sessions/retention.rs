@@ -0,0 +1,222 @@
+//! Deleting old sessions and measuring their disk usage
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use tracing::{debug, info, warn};
+
+use super::SessionStore;
+use crate::errors::ClaudeSDKError;
+
+/// Which sessions to keep when pruning
+///
+/// A session is deleted when it breaks any of the limits that are set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Delete sessions not modified for longer than this
+    pub max_age: Option<Duration>,
+
+    /// Keep at most this many of the most recently modified sessions per project
+    pub max_sessions_per_project: Option<usize>,
+
+    /// Report what would be deleted without deleting anything
+    pub dry_run: bool,
+}
+
+impl RetentionPolicy {
+    /// A policy that keeps everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delete sessions older than `max_age`
+    pub fn max_age(max_age: Duration) -> Self {
+        Self {
+            max_age: Some(max_age),
+            ..Self::default()
+        }
+    }
+
+    /// Keep the `count` most recent sessions of each project
+    pub fn keep_latest(count: usize) -> Self {
+        Self {
+            max_sessions_per_project: Some(count),
+            ..Self::default()
+        }
+    }
+}
+
+/// A session removed by pruning
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrunedSession {
+    pub session_id: String,
+    pub project_dir: PathBuf,
+    /// Bytes freed, including the session's side directory
+    pub size_bytes: u64,
+}
+
+/// Outcome of [`SessionStore::prune`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    pub deleted: Vec<PrunedSession>,
+    /// Number of sessions left in place
+    pub kept: usize,
+    pub freed_bytes: u64,
+    /// Whether this was a dry run that deleted nothing
+    pub dry_run: bool,
+}
+
+/// Disk space taken by stored sessions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub total_bytes: u64,
+    pub session_count: usize,
+    /// Bytes per project directory
+    pub by_project: BTreeMap<PathBuf, u64>,
+}
+
+/// A session's files on disk
+struct StoredSession {
+    session_id: String,
+    transcript: PathBuf,
+    modified: SystemTime,
+    size_bytes: u64,
+}
+
+impl SessionStore {
+    /// Delete a session's transcript and side directory from whichever project holds it
+    ///
+    /// Returns whether the session existed.
+    pub fn delete_session(&self, session_id: &str) -> Result<bool, ClaudeSDKError> {
+        let Some(transcript) = self.find_session(session_id)? else {
+            debug!(session_id, "No session to delete");
+            return Ok(false);
+        };
+        remove_session_files(&transcript)?;
+        info!(session_id, path = %transcript.display(), "Deleted session");
+        Ok(true)
+    }
+
+    /// Delete the sessions of every project that `policy` does not keep
+    pub fn prune(&self, policy: &RetentionPolicy) -> Result<PruneReport, ClaudeSDKError> {
+        let mut report = PruneReport {
+            dry_run: policy.dry_run,
+            ..PruneReport::default()
+        };
+        for project_dir in self.project_dirs()? {
+            prune_project_dir(&project_dir, policy, &mut report)?;
+        }
+        info!(
+            deleted = report.deleted.len(),
+            kept = report.kept,
+            freed_bytes = report.freed_bytes,
+            dry_run = report.dry_run,
+            "Pruned sessions"
+        );
+        Ok(report)
+    }
+
+    /// Delete the sessions of the project at `cwd` that `policy` does not keep
+    pub fn prune_project(&self, cwd: &Path, policy: &RetentionPolicy) -> Result<PruneReport, ClaudeSDKError> {
+        let mut report = PruneReport {
+            dry_run: policy.dry_run,
+            ..PruneReport::default()
+        };
+        prune_project_dir(&self.project_dir(cwd), policy, &mut report)?;
+        Ok(report)
+    }
+
+    /// Disk space taken by every project's sessions
+    pub fn disk_usage(&self) -> Result<DiskUsage, ClaudeSDKError> {
+        let mut usage = DiskUsage::default();
+        for project_dir in self.project_dirs()? {
+            let sessions = stored_sessions(&project_dir)?;
+            let project_bytes = dir_size(&project_dir)?;
+            usage.session_count += sessions.len();
+            usage.total_bytes += project_bytes;
+            usage.by_project.insert(project_dir, project_bytes);
+        }
+        debug!(total_bytes = usage.total_bytes, sessions = usage.session_count, "Measured session disk usage");
+        Ok(usage)
+    }
+}
+
+fn prune_project_dir(project_dir: &Path, policy: &RetentionPolicy, report: &mut PruneReport) -> Result<(), ClaudeSDKError> {
+    let mut sessions = stored_sessions(project_dir)?;
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.modified));
+
+    let cutoff = policy.max_age.and_then(|max_age| SystemTime::now().checked_sub(max_age));
+    for (rank, session) in sessions.into_iter().enumerate() {
+        let too_old = cutoff.is_some_and(|cutoff| session.modified < cutoff);
+        let over_count = policy.max_sessions_per_project.is_some_and(|max| rank >= max);
+        if !too_old && !over_count {
+            report.kept += 1;
+            continue;
+        }
+
+        debug!(
+            session_id = %session.session_id,
+            too_old,
+            over_count,
+            dry_run = policy.dry_run,
+            "Pruning session"
+        );
+        if !policy.dry_run {
+            remove_session_files(&session.transcript)?;
+        }
+        report.freed_bytes += session.size_bytes;
+        report.deleted.push(PrunedSession {
+            session_id: session.session_id,
+            project_dir: project_dir.to_path_buf(),
+            size_bytes: session.size_bytes,
+        });
+    }
+    Ok(())
+}
+
+fn stored_sessions(project_dir: &Path) -> Result<Vec<StoredSession>, ClaudeSDKError> {
+    let mut sessions = Vec::new();
+    for transcript in SessionStore::transcript_paths(project_dir)? {
+        let Some(session_id) = transcript.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        let metadata = std::fs::metadata(&transcript)?;
+        let side_dir = transcript.with_extension("");
+        let side_bytes = if side_dir.is_dir() { dir_size(&side_dir)? } else { 0 };
+        sessions.push(StoredSession {
+            session_id,
+            modified: metadata.modified()?,
+            size_bytes: metadata.len() + side_bytes,
+            transcript,
+        });
+    }
+    Ok(sessions)
+}
+
+/// Remove a transcript and the directory the CLI keeps alongside it, if any
+fn remove_session_files(transcript: &Path) -> Result<(), ClaudeSDKError> {
+    std::fs::remove_file(transcript)?;
+    let side_dir = transcript.with_extension("");
+    if side_dir.is_dir() {
+        if let Err(e) = std::fs::remove_dir_all(&side_dir) {
+            warn!(path = %side_dir.display(), error = %e, "Failed to remove session directory");
+            return Err(e.into());
+        }
+    }
+    Ok(())
+}
+
+fn dir_size(dir: &Path) -> Result<u64, ClaudeSDKError> {
+    let mut total = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            total += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
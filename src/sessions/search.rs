@@ -0,0 +1,417 @@
+//! Full-text search over session transcripts
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use super::{SessionStore, Transcript};
+use crate::errors::{ClaudeSDKError, ConfigError};
+use crate::types::{ContentBlock, Message};
+
+/// Longest snippet returned for a match, in characters
+const SNIPPET_CHARS: usize = 160;
+
+/// Most snippets returned per session
+const MAX_SNIPPETS: usize = 3;
+
+/// Tool input fields that hold file paths
+const PATH_FIELDS: [&str; 3] = ["file_path", "notebook_path", "path"];
+
+/// What part of a transcript a passage came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PassageKind {
+    Prompt,
+    Summary,
+    AssistantText,
+    ToolName,
+    FilePath,
+}
+
+impl PassageKind {
+    /// How much a match in this kind of passage counts towards the score
+    fn weight(self) -> f64 {
+        match self {
+            PassageKind::Prompt | PassageKind::Summary => 3.0,
+            PassageKind::ToolName | PassageKind::FilePath => 2.0,
+            PassageKind::AssistantText => 1.0,
+        }
+    }
+}
+
+/// A searchable piece of a transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Passage {
+    pub kind: PassageKind,
+    pub text: String,
+}
+
+/// A matching piece of text within a search hit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    pub kind: PassageKind,
+    pub text: String,
+}
+
+/// A session matching a search
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub session_id: String,
+    pub path: PathBuf,
+    pub cwd: Option<PathBuf>,
+    pub score: f64,
+    pub snippets: Vec<Snippet>,
+}
+
+/// What [`SearchIndex::update`] changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexUpdate {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedSession {
+    path: PathBuf,
+    cwd: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    size_bytes: u64,
+    passages: Vec<Passage>,
+}
+
+/// Inverted index over the prompts, assistant text, tool names and file
+/// paths of stored sessions
+///
+/// All query terms must match for a session to be returned; a term matches
+/// any indexed word it is a prefix of. The index can be saved and loaded so
+/// that [`SearchIndex::update`] only re-reads transcripts that changed.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    sessions: HashMap<String, IndexedSession>,
+    /// Term -> session id -> weighted term frequency
+    postings: BTreeMap<String, HashMap<String, f64>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedIndex {
+    sessions: HashMap<String, IndexedSession>,
+}
+
+impl SearchIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load an index saved with [`SearchIndex::save`], or start empty if the file is missing
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ClaudeSDKError> {
+        let path = path.as_ref();
+        let contents = match std::fs::read(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let saved: SavedIndex = serde_json::from_slice(&contents)
+            .map_err(|e| ConfigError::with_path(format!("Invalid search index: {}", e), path))?;
+
+        let mut index = Self::new();
+        for (session_id, session) in saved.sessions {
+            index.insert(session_id, session);
+        }
+        info!(path = %path.display(), sessions = index.len(), "Loaded search index");
+        Ok(index)
+    }
+
+    /// Save the index so a later [`SearchIndex::load`] can skip unchanged sessions
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ClaudeSDKError> {
+        let path = path.as_ref();
+        let saved = SavedIndex {
+            sessions: self.sessions.clone(),
+        };
+        let contents = serde_json::to_vec(&saved)
+            .map_err(|e| ConfigError::with_path(format!("Failed to serialize search index: {}", e), path))?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+        debug!(path = %path.display(), sessions = self.len(), "Saved search index");
+        Ok(())
+    }
+
+    /// Number of indexed sessions
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Whether no sessions are indexed
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Bring the index up to date with every session in `store`
+    ///
+    /// Only transcripts that are new or whose size or modification time
+    /// changed are read; sessions that no longer exist are dropped.
+    pub fn update(&mut self, store: &SessionStore) -> Result<IndexUpdate, ClaudeSDKError> {
+        let mut update = IndexUpdate::default();
+        let mut seen = HashSet::new();
+
+        for project_dir in store.project_dirs()? {
+            for path in SessionStore::transcript_paths(&project_dir)? {
+                let Some(session_id) = path.file_stem().map(|s| s.to_string_lossy().into_owned()) else {
+                    continue;
+                };
+                let metadata = std::fs::metadata(&path)?;
+                let modified = metadata.modified().ok();
+                seen.insert(session_id.clone());
+
+                let existing = self.sessions.get(&session_id);
+                if existing.is_some_and(|s| s.path == path && s.modified == modified && s.size_bytes == metadata.len()) {
+                    update.unchanged += 1;
+                    continue;
+                }
+                let is_new = existing.is_none();
+
+                match Transcript::read(&path) {
+                    Ok(transcript) => {
+                        self.index_transcript(&transcript);
+                        if let Some(session) = self.sessions.get_mut(&session_id) {
+                            session.modified = modified;
+                        }
+                        if is_new {
+                            update.added += 1;
+                        } else {
+                            update.updated += 1;
+                        }
+                    }
+                    Err(e) => warn!(path = %path.display(), error = %e, "Skipping unreadable transcript"),
+                }
+            }
+        }
+
+        let stale: Vec<String> = self.sessions.keys().filter(|id| !seen.contains(*id)).cloned().collect();
+        for session_id in stale {
+            self.remove(&session_id);
+            update.removed += 1;
+        }
+
+        info!(
+            added = update.added,
+            updated = update.updated,
+            removed = update.removed,
+            unchanged = update.unchanged,
+            "Updated search index"
+        );
+        Ok(update)
+    }
+
+    /// Add or replace a session in the index
+    pub fn index_transcript(&mut self, transcript: &Transcript) {
+        let info = &transcript.info;
+        let session = IndexedSession {
+            path: info.path.clone(),
+            cwd: info.cwd.clone(),
+            modified: std::fs::metadata(&info.path).and_then(|m| m.modified()).ok(),
+            size_bytes: info.size_bytes,
+            passages: extract_passages(transcript),
+        };
+        debug!(
+            session_id = %info.session_id,
+            passages = session.passages.len(),
+            "Indexing session"
+        );
+        self.remove(&info.session_id);
+        self.insert(info.session_id.clone(), session);
+    }
+
+    /// Drop a session from the index
+    pub fn remove(&mut self, session_id: &str) -> bool {
+        let Some(session) = self.sessions.remove(session_id) else {
+            return false;
+        };
+        for passage in &session.passages {
+            for term in tokenize(&passage.text) {
+                if let Some(sessions) = self.postings.get_mut(&term) {
+                    sessions.remove(session_id);
+                    if sessions.is_empty() {
+                        self.postings.remove(&term);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Sessions matching every term of `query`, best first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let terms: Vec<String> = tokenize(query).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scores: Option<HashMap<&str, f64>> = None;
+        for term in &terms {
+            let mut term_scores: HashMap<&str, f64> = HashMap::new();
+            for (_, sessions) in self.postings.range(term.clone()..).take_while(|(t, _)| t.starts_with(term.as_str())) {
+                for (session_id, weight) in sessions {
+                    *term_scores.entry(session_id.as_str()).or_default() += weight;
+                }
+            }
+            // Rarer terms say more about a session
+            let idf = (1.0 + self.sessions.len() as f64 / (1.0 + term_scores.len() as f64)).ln();
+            scores = Some(match scores {
+                None => term_scores.into_iter().map(|(id, s)| (id, s * idf)).collect(),
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(id, s)| term_scores.get(id).map(|t| (id, s + t * idf)))
+                    .collect(),
+            });
+        }
+
+        let mut hits: Vec<SearchHit> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(session_id, score)| {
+                let session = self.sessions.get(session_id)?;
+                Some(SearchHit {
+                    session_id: session_id.to_string(),
+                    path: session.path.clone(),
+                    cwd: session.cwd.clone(),
+                    score,
+                    snippets: snippets(&session.passages, &terms),
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.session_id.cmp(&b.session_id)));
+        hits.truncate(limit);
+        debug!(query, hits = hits.len(), "Searched sessions");
+        hits
+    }
+
+    fn insert(&mut self, session_id: String, session: IndexedSession) {
+        for passage in &session.passages {
+            for term in tokenize(&passage.text) {
+                *self
+                    .postings
+                    .entry(term)
+                    .or_default()
+                    .entry(session_id.clone())
+                    .or_default() += passage.kind.weight();
+            }
+        }
+        self.sessions.insert(session_id, session);
+    }
+}
+
+/// Searchable passages of a transcript
+fn extract_passages(transcript: &Transcript) -> Vec<Passage> {
+    let mut passages = Vec::new();
+    let mut push = |kind, text: &str| {
+        if !text.trim().is_empty() {
+            passages.push(Passage {
+                kind,
+                text: text.to_string(),
+            });
+        }
+    };
+
+    if let Some(summary) = &transcript.info.summary {
+        push(PassageKind::Summary, summary);
+    }
+    for entry in &transcript.entries {
+        match &entry.message {
+            Some(Message::User(user)) if !entry.is_meta() => push(PassageKind::Prompt, &user.content),
+            Some(Message::Assistant(assistant)) => {
+                for block in &assistant.content {
+                    match block {
+                        ContentBlock::Text(text) => push(PassageKind::AssistantText, &text.text),
+                        ContentBlock::ToolUse(tool) => {
+                            push(PassageKind::ToolName, &tool.name);
+                            for field in PATH_FIELDS {
+                                if let Some(path) = tool.input.get(field).and_then(|v| v.as_str()) {
+                                    push(PassageKind::FilePath, path);
+                                }
+                            }
+                            if let Some(edits) = tool.input.get("edits").and_then(|v| v.as_array()) {
+                                for path in edits.iter().filter_map(|e| e.get("file_path")?.as_str()) {
+                                    push(PassageKind::FilePath, path);
+                                }
+                            }
+                        }
+                        ContentBlock::ToolResult(_) => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    passages
+}
+
+/// Lowercased words of `text`
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Excerpts of the passages that mention any of `terms`
+fn snippets(passages: &[Passage], terms: &[String]) -> Vec<Snippet> {
+    let mut snippets = Vec::new();
+    for passage in passages {
+        let (lower, origins) = lowercase_with_offsets(&passage.text);
+        let Some(position) = terms.iter().filter_map(|term| lower.find(term.as_str())).min() else {
+            continue;
+        };
+        snippets.push(Snippet {
+            kind: passage.kind,
+            text: excerpt(&passage.text, origins[position]),
+        });
+        if snippets.len() == MAX_SNIPPETS {
+            break;
+        }
+    }
+    snippets
+}
+
+/// `text` lowercased, with the byte offset in `text` of the character each lowercased byte came from
+///
+/// Lowercasing can change a character's length (`İ` becomes two characters,
+/// the Kelvin sign shrinks to `k`), so offsets have to be mapped back.
+fn lowercase_with_offsets(text: &str) -> (String, Vec<usize>) {
+    let mut lower = String::with_capacity(text.len());
+    let mut origins = Vec::with_capacity(text.len());
+    for (offset, c) in text.char_indices() {
+        let start = lower.len();
+        lower.extend(c.to_lowercase());
+        origins.resize(origins.len() + lower.len() - start, offset);
+    }
+    (lower, origins)
+}
+
+/// About [`SNIPPET_CHARS`] characters of `text` around byte offset `position`
+fn excerpt(text: &str, position: usize) -> String {
+    let char_index = text[..position].chars().count();
+    let start = char_index.saturating_sub(SNIPPET_CHARS / 3);
+    let total = text.chars().count();
+    let end = (start + SNIPPET_CHARS).min(total);
+
+    let mut excerpt: String = text
+        .chars()
+        .skip(start)
+        .take(end - start)
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .collect();
+    if start > 0 {
+        excerpt.insert(0, '…');
+    }
+    if end < total {
+        excerpt.push('…');
+    }
+    excerpt
+}
@@ -0,0 +1,269 @@
+//! Incremental JSON framing over raw CLI output bytes.
+
+use tracing::{debug, trace, warn};
+
+/// A unit of output produced by [`JsonFramer`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// A complete top-level JSON value
+    Complete(Vec<u8>),
+    /// A fragment that can never become valid JSON; framing resumes after it
+    Malformed {
+        fragment: Vec<u8>,
+        reason: &'static str,
+    },
+}
+
+/// Splits a byte stream into complete top-level JSON values.
+///
+/// The framer tracks nesting depth and string/escape state, so every byte is
+/// scanned exactly once regardless of how a message is split across lines or
+/// reads. Braces inside string values never affect framing.
+///
+/// A frame may only begin with `{` or `[` as the first non-whitespace byte of
+/// a line; any other line seen between frames is treated as non-JSON output
+/// and skipped.
+///
+/// A frame in progress is abandoned as [`Frame::Malformed`] when a raw newline
+/// appears inside a string, when a line starts a new object where no value can
+/// follow, or when the frame spans more than the configured number of lines.
+#[derive(Debug)]
+pub struct JsonFramer {
+    buffer: Vec<u8>,
+    /// Offset of the first unconsumed byte in `buffer`
+    start: usize,
+    /// Offset of the next byte to scan
+    scan_pos: usize,
+    /// Offset of the current frame's opening byte, if a frame is in progress
+    frame_start: Option<usize>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    at_line_start: bool,
+    skipping_line: bool,
+    /// Last non-whitespace byte of the current frame outside of strings
+    last_significant: u8,
+    /// Newlines seen since the current frame started
+    frame_lines: usize,
+    max_frame_lines: usize,
+}
+
+impl Default for JsonFramer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonFramer {
+    /// Create an empty framer
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            start: 0,
+            scan_pos: 0,
+            frame_start: None,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            at_line_start: true,
+            skipping_line: false,
+            last_significant: 0,
+            frame_lines: 0,
+            max_frame_lines: usize::MAX,
+        }
+    }
+
+    /// Limit the number of lines a single frame may span
+    pub fn with_max_frame_lines(mut self, max_frame_lines: usize) -> Self {
+        self.max_frame_lines = max_frame_lines;
+        self
+    }
+
+    /// Set the number of lines a single frame may span
+    pub fn set_max_frame_lines(&mut self, max_frame_lines: usize) {
+        self.max_frame_lines = max_frame_lines;
+    }
+
+    /// Append raw bytes read from the CLI
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Return the next complete or malformed frame, if one is available
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        while self.scan_pos < self.buffer.len() {
+            let byte = self.buffer[self.scan_pos];
+            let pos = self.scan_pos;
+            self.scan_pos += 1;
+
+            if self.frame_start.is_none() {
+                self.scan_between_frames(byte, pos);
+                continue;
+            }
+
+            if self.in_string {
+                if byte == b'\n' {
+                    // JSON strings cannot contain raw newlines
+                    let frame = self.take_malformed(pos, "unterminated string");
+                    self.at_line_start = true;
+                    return Some(frame);
+                }
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+
+            if byte == b'\n' {
+                self.at_line_start = true;
+                self.frame_lines += 1;
+                if self.frame_lines > self.max_frame_lines {
+                    warn!(
+                        frame_lines = self.frame_lines,
+                        max_frame_lines = self.max_frame_lines,
+                        "JSON frame exceeded line budget"
+                    );
+                    let frame = self.take_malformed(pos, "line budget exceeded");
+                    self.at_line_start = true;
+                    return Some(frame);
+                }
+                continue;
+            }
+            if byte.is_ascii_whitespace() {
+                continue;
+            }
+
+            if self.at_line_start
+                && byte == b'{'
+                && !matches!(self.last_significant, b'[' | b',' | b':')
+            {
+                // An object cannot follow here, so this line starts a new message
+                let frame = self.take_malformed(pos, "interrupted by a new object");
+                self.begin_frame(pos);
+                return Some(frame);
+            }
+            self.at_line_start = false;
+            self.last_significant = byte;
+
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => self.depth += 1,
+                b'}' | b']' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.depth == 0 {
+                        let frame_start = self.frame_start.take().unwrap_or(pos);
+                        let frame = self.buffer[frame_start..=pos].to_vec();
+                        self.start = self.scan_pos;
+                        // Allow another value to follow on the same line
+                        self.at_line_start = true;
+                        self.compact();
+                        trace!(frame_length = frame.len(), "Framed complete JSON value");
+                        return Some(Frame::Complete(frame));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if self.frame_start.is_none() {
+            // Nothing worth keeping between frames
+            self.start = self.scan_pos;
+            self.compact();
+        }
+        None
+    }
+
+    /// Number of bytes held for the frame currently being assembled
+    pub fn pending_len(&self) -> usize {
+        match self.frame_start {
+            Some(frame_start) => self.buffer.len() - frame_start,
+            None => 0,
+        }
+    }
+
+    /// Whether a partially received frame is buffered
+    pub fn has_partial_frame(&self) -> bool {
+        self.frame_start.is_some()
+    }
+
+    /// Bytes of the frame currently being assembled
+    pub fn partial_frame(&self) -> &[u8] {
+        match self.frame_start {
+            Some(frame_start) => &self.buffer[frame_start..],
+            None => &[],
+        }
+    }
+
+    /// Drop the frame in progress and skip the rest of the current line, if any
+    pub fn discard_partial_frame(&mut self) {
+        debug!(discarded_bytes = self.pending_len(), "Discarding partial JSON frame");
+        self.reset_frame_state();
+        let mid_line = self.scan_pos > 0 && self.buffer[self.scan_pos - 1] != b'\n';
+        self.skipping_line = mid_line;
+        self.at_line_start = !mid_line;
+        self.start = self.scan_pos;
+        self.compact();
+    }
+
+    fn scan_between_frames(&mut self, byte: u8, pos: usize) {
+        if byte == b'\n' {
+            self.at_line_start = true;
+            self.skipping_line = false;
+            return;
+        }
+        if self.skipping_line || byte.is_ascii_whitespace() {
+            return;
+        }
+        if self.at_line_start && (byte == b'{' || byte == b'[') {
+            self.begin_frame(pos);
+        } else {
+            self.skipping_line = true;
+            self.at_line_start = false;
+        }
+    }
+
+    fn begin_frame(&mut self, pos: usize) {
+        self.reset_frame_state();
+        self.frame_start = Some(pos);
+        self.depth = 1;
+        self.last_significant = self.buffer[pos];
+        self.at_line_start = false;
+    }
+
+    fn reset_frame_state(&mut self) {
+        self.frame_start = None;
+        self.depth = 0;
+        self.in_string = false;
+        self.escaped = false;
+        self.last_significant = 0;
+        self.frame_lines = 0;
+    }
+
+    /// End the frame in progress at `end` (exclusive) as a malformed fragment
+    fn take_malformed(&mut self, end: usize, reason: &'static str) -> Frame {
+        let frame_start = self.frame_start.unwrap_or(end);
+        let fragment = self.buffer[frame_start..end].to_vec();
+        debug!(fragment_length = fragment.len(), reason, "Abandoning malformed JSON frame");
+        self.reset_frame_state();
+        self.skipping_line = false;
+        self.start = end;
+        Frame::Malformed { fragment, reason }
+    }
+
+    /// Release consumed bytes once they make up most of the buffer
+    fn compact(&mut self) {
+        if self.start == 0 || self.start < self.buffer.len() / 2 {
+            return;
+        }
+        self.buffer.drain(..self.start);
+        self.scan_pos -= self.start;
+        if let Some(frame_start) = self.frame_start.as_mut() {
+            *frame_start -= self.start;
+        }
+        self.start = 0;
+    }
+}
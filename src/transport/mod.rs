@@ -5,8 +5,12 @@ use std::collections::HashMap;
 use std::pin::Pin;
 use crate::errors::ClaudeSDKError;
 
+pub mod framer;
 pub mod subprocess_cli;
 
+/// Stream of raw JSON messages produced by a transport
+pub type RawMessageStream<'a> = Pin<Box<dyn Stream<Item = Result<HashMap<String, serde_json::Value>, ClaudeSDKError>> + Send + 'a>>;
+
 /// Abstract transport for Claude communication
 #[async_trait::async_trait]
 pub trait Transport {
@@ -27,7 +31,7 @@ pub trait Transport {
     }
     
     /// Receive messages from Claude
-    fn receive_messages(&mut self) -> Pin<Box<dyn Stream<Item = Result<HashMap<String, serde_json::Value>, ClaudeSDKError>> + Send + '_>>;
+    fn receive_messages(&mut self) -> RawMessageStream<'_>;
     
     /// Check if transport is connected
     #[allow(dead_code)]
@@ -1,23 +1,47 @@
 //! Subprocess transport implementation using Claude Code CLI.
 
-use futures::Stream;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio_stream::{wrappers::LinesStream, StreamExt};
+use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn, instrument};
 use async_stream;
+use futures::Stream;
+use tokio_stream::StreamExt;
 
 use crate::{
     errors::*,
-    types::{ClaudeCodeOptions, PermissionMode},
-    transport::Transport,
+    types::{ClaudeCodeOptions, PermissionMode, SettingSource, Settings},
+    transport::{framer::{Frame, JsonFramer}, RawMessageStream, Transport},
     SafetyLimits, SafetyError,
 };
 
+/// Size of each read from the CLI's stdout
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Most recent stderr output kept for error classification
+const MAX_STDERR_CAPTURE: usize = 64 * 1024;
+
+/// How long to wait for stderr to drain once stdout has closed
+const STDERR_DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A message decoded from the CLI's output, with the bytes it was decoded from
+#[derive(Debug, Clone)]
+pub struct DecodedFrame {
+    /// The decoded JSON object
+    pub data: HashMap<String, serde_json::Value>,
+    /// The frame exactly as the CLI wrote it
+    pub bytes: Vec<u8>,
+}
+
+/// Stream of decoded messages together with their original bytes
+pub type DecodedFrameStream<'a> = Pin<Box<dyn Stream<Item = Result<DecodedFrame, ClaudeSDKError>> + Send + 'a>>;
+
 /// Subprocess transport using Claude Code CLI
 pub struct SubprocessCLITransport {
     prompt: String,
@@ -25,8 +49,10 @@ pub struct SubprocessCLITransport {
     cli_path: String,
     cwd: Option<PathBuf>,
     process: Option<Child>,
+    stderr_output: Arc<Mutex<String>>,
+    stderr_task: Option<JoinHandle<()>>,
     safety_limits: SafetyLimits,
-    json_buffer: String,
+    framer: JsonFramer,
 }
 
 impl SubprocessCLITransport {
@@ -48,6 +74,9 @@ impl SubprocessCLITransport {
         let cli_path = if let Some(path) = cli_path {
             debug!(provided_path = path, "Using provided CLI path");
             path.to_string()
+        } else if let Some(path) = &options.cli_path {
+            debug!(provided_path = %path.display(), "Using CLI path from options");
+            path.to_string_lossy().into_owned()
         } else {
             debug!("Searching for CLI path");
             Self::find_cli()?
@@ -62,50 +91,147 @@ impl SubprocessCLITransport {
             cli_path,
             cwd,
             process: None,
+            stderr_output: Arc::new(Mutex::new(String::new())),
+            stderr_task: None,
             safety_limits: SafetyLimits::default(),
-            json_buffer: String::new(),
+            framer: JsonFramer::new().with_max_frame_lines(SafetyLimits::default().max_json_lines),
         })
     }
     
     /// Set custom safety limits for this transport
     pub fn with_safety_limits(mut self, limits: SafetyLimits) -> Self {
         info!(?limits, "Setting custom safety limits");
+        self.framer.set_max_frame_lines(limits.max_json_lines);
         self.safety_limits = limits;
         self
     }
     
-    /// Try to parse accumulated JSON buffer, handling multiline JSON
+    /// Stderr output captured from the CLI, keeping the most recent 64KB
+    ///
+    /// Waits briefly for the stderr reader to finish so output written just
+    /// before the process exited is included.
+    pub async fn captured_stderr(&mut self) -> String {
+        if let Some(task) = self.stderr_task.take() {
+            if tokio::time::timeout(STDERR_DRAIN_TIMEOUT, task).await.is_err() {
+                debug!("Timed out waiting for stderr to drain");
+            }
+        }
+        self.stderr_output
+            .lock()
+            .map(|output| output.clone())
+            .unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    /// Try to parse a partially framed JSON value, e.g. when the stream ends
     pub fn try_parse_json_buffer(&mut self) -> Option<Result<HashMap<String, serde_json::Value>, ClaudeSDKError>> {
-        if self.json_buffer.is_empty() {
+        self.try_decode_json_buffer().map(|result| result.map(|frame| frame.data))
+    }
+
+    /// Like [`Self::try_parse_json_buffer`], keeping the buffered bytes
+    fn try_decode_json_buffer(&mut self) -> Option<Result<DecodedFrame, ClaudeSDKError>> {
+        if !self.framer.has_partial_frame() {
             return None;
         }
-        
-        // Safety check: buffer size
-        let buffer_size = self.json_buffer.len();
-        if !self.safety_limits.is_line_size_safe(buffer_size) {
+
+        let partial = self.framer.partial_frame();
+        match serde_json::from_slice::<HashMap<String, serde_json::Value>>(partial) {
+            Ok(data) => {
+                debug!(fields_count = data.len(), "Parsed trailing JSON buffer");
+                let bytes = partial.to_vec();
+                self.framer.discard_partial_frame();
+                Some(Ok(DecodedFrame { data, bytes }))
+            }
+            Err(e) => {
+                debug!(
+                    error = %e,
+                    buffer_length = partial.len(),
+                    "JSON buffer not yet complete, waiting for more data"
+                );
+                None
+            }
+        }
+    }
+
+    /// Feed raw bytes from the CLI's stdout into the JSON framer
+    pub fn process_bytes(&mut self, bytes: &[u8]) {
+        self.framer.push(bytes);
+    }
+
+    /// Process a single line and return the next complete message, if any
+    pub fn process_line(&mut self, line: String) -> Option<Result<HashMap<String, serde_json::Value>, ClaudeSDKError>> {
+        debug!(line_length = line.len(), "Processing line from subprocess");
+        self.framer.push(line.as_bytes());
+        self.framer.push(b"\n");
+        self.next_message()
+    }
+
+    /// Return the next complete message from framed output, if one is available
+    ///
+    /// Call repeatedly until it returns `None` to drain every message from the
+    /// bytes fed so far.
+    pub fn next_message(&mut self) -> Option<Result<HashMap<String, serde_json::Value>, ClaudeSDKError>> {
+        self.next_decoded().map(|result| result.map(|frame| frame.data))
+    }
+
+    /// Like [`Self::next_message`], keeping the bytes each message was decoded from
+    pub fn next_decoded(&mut self) -> Option<Result<DecodedFrame, ClaudeSDKError>> {
+        match self.framer.next_frame() {
+            Some(Frame::Complete(frame)) => return Some(self.parse_frame(frame)),
+            Some(Frame::Malformed { fragment, reason }) => {
+                warn!(
+                    reason,
+                    fragment_preview = %self.safety_limits.safe_log_preview(&String::from_utf8_lossy(&fragment)),
+                    "Skipping malformed JSON fragment and resynchronizing"
+                );
+                return Some(Err(Self::malformed_fragment_error(&fragment, reason)));
+            }
+            None => {}
+        }
+
+        // Safety check: size of the value still being assembled
+        let pending = self.framer.pending_len();
+        if !self.safety_limits.is_line_size_safe(pending) {
             error!(
-                buffer_size = buffer_size,
+                buffer_size = pending,
                 limit = self.safety_limits.max_line_size,
                 "JSON buffer exceeds safety limit"
             );
-            self.json_buffer.clear(); // Clear to prevent memory issues
+            self.framer.discard_partial_frame(); // Clear to prevent memory issues
             return Some(Err(ClaudeSDKError::Safety(SafetyError::LineTooLarge {
-                actual: buffer_size,
+                actual: pending,
                 limit: self.safety_limits.max_line_size,
             })));
         }
-        
-        debug!(
-            buffer_length = buffer_size,
-            buffer_preview = %self.safety_limits.safe_log_preview(&self.json_buffer),
-            "Attempting to parse JSON buffer"
-        );
-        
-        // Safe JSON parsing with timeout monitoring
+
+        if pending > self.safety_limits.max_line_size / 2 {
+            warn!(
+                buffer_size = pending,
+                max_size = self.safety_limits.max_line_size,
+                "JSON buffer growing large without a complete value, might be malformed"
+            );
+        }
+
+        None // No complete JSON yet, continue accumulating
+    }
+
+    /// Build a decode error for a fragment the framer gave up on
+    fn malformed_fragment_error(fragment: &[u8], reason: &'static str) -> ClaudeSDKError {
+        let original_error = match serde_json::from_slice::<serde_json::Value>(fragment) {
+            Err(e) => e,
+            Ok(_) => serde_json::Error::io(std::io::Error::new(std::io::ErrorKind::InvalidData, reason)),
+        };
+        ClaudeSDKError::CLIJSONDecode(CLIJSONDecodeError::new(
+            String::from_utf8_lossy(fragment),
+            original_error,
+        ))
+    }
+
+    /// Parse one complete JSON frame into a message map
+    fn parse_frame(&self, frame: Vec<u8>) -> Result<DecodedFrame, ClaudeSDKError> {
         let parse_start = std::time::Instant::now();
-        let parse_result = serde_json::from_str::<HashMap<String, serde_json::Value>>(&self.json_buffer);
+        let parse_result = serde_json::from_slice::<HashMap<String, serde_json::Value>>(&frame);
         let parse_duration = parse_start.elapsed();
-        
+
         if parse_duration.as_millis() > self.safety_limits.json_parse_timeout_ms as u128 {
             warn!(
                 duration_ms = parse_duration.as_millis(),
@@ -113,16 +239,16 @@ impl SubprocessCLITransport {
                 "JSON parsing took longer than expected"
             );
         }
-        
+
         match parse_result {
             Ok(data) => {
                 debug!(
                     fields_count = data.len(),
                     parse_duration_ms = parse_duration.as_millis(),
-                    buffer_length = buffer_size,
-                    "Successfully parsed multiline JSON message"
+                    frame_length = frame.len(),
+                    "Successfully parsed JSON message"
                 );
-                
+
                 // Check if this contains large text content
                 if let Some(message_obj) = data.get("message") {
                     if let Some(content_arr) = message_obj.get("content").and_then(|c| c.as_array()) {
@@ -134,91 +260,22 @@ impl SubprocessCLITransport {
                                         text_size = text_size,
                                         limit = self.safety_limits.max_text_block_size,
                                         text_preview = %self.safety_limits.safe_log_preview(text),
-                                        "Large text block detected in multiline JSON"
+                                        "Large text block detected in JSON message"
                                     );
                                 }
                             }
                         }
                     }
                 }
-                
-                self.json_buffer.clear(); // Clear buffer after successful parse
-                Some(Ok(data))
-            }
-            Err(e) => {
-                // For incomplete JSON, we don't immediately error - we wait for more data
-                debug!(
-                    error = %e,
-                    buffer_preview = %self.safety_limits.safe_log_preview(&self.json_buffer),
-                    "JSON buffer not yet complete, waiting for more data"
-                );
-                None // Return None to indicate we need more data
+
+                Ok(DecodedFrame { data, bytes: frame })
             }
+            Err(e) => Err(ClaudeSDKError::CLIJSONDecode(CLIJSONDecodeError::new(
+                String::from_utf8_lossy(&frame),
+                e,
+            ))),
         }
     }
-    
-    /// Process a single line and update JSON buffer state
-    pub fn process_line(&mut self, line: String) -> Option<Result<HashMap<String, serde_json::Value>, ClaudeSDKError>> {
-        let line = line.trim();
-        if line.is_empty() {
-            debug!("Skipping empty line");
-            return None;
-        }
-        
-        // Safety check: individual line size
-        let line_size = line.len();
-        if !self.safety_limits.is_line_size_safe(line_size) {
-            error!(
-                line_size = line_size,
-                limit = self.safety_limits.max_line_size,
-                "Single line exceeds safety limit"
-            );
-            return Some(Err(ClaudeSDKError::Safety(SafetyError::LineTooLarge {
-                actual: line_size,
-                limit: self.safety_limits.max_line_size,
-            })));
-        }
-        
-        debug!(line_length = line_size, "Processing line from subprocess");
-        
-        // Check if this line looks like the start of JSON
-        let looks_like_json_start = line.starts_with('{') || line.starts_with('[');
-        let looks_like_json_continuation = !self.json_buffer.is_empty();
-        
-        if looks_like_json_start && self.json_buffer.is_empty() {
-            // Starting a new JSON object/array
-            debug!("Starting new JSON buffer");
-            self.json_buffer = line.to_string();
-        } else if looks_like_json_continuation {
-            // Continuing a JSON object/array
-            debug!("Appending to existing JSON buffer");
-            self.json_buffer.push('\n');
-            self.json_buffer.push_str(line);
-        } else if !looks_like_json_start {
-            // Non-JSON line, log and skip
-            debug!(
-                line_preview = %self.safety_limits.safe_log_preview(line),
-                "Skipping non-JSON line"
-            );
-            return None;
-        }
-        
-        // Try to parse the current buffer
-        if let Some(result) = self.try_parse_json_buffer() {
-            return Some(result);
-        }
-        
-        // Check if buffer is getting too large without successful parse
-        if self.json_buffer.len() > self.safety_limits.max_line_size / 2 {
-            warn!(
-                buffer_size = self.json_buffer.len(),
-                max_size = self.safety_limits.max_line_size,
-                "JSON buffer growing large without successful parse, might be malformed"
-            );
-        }
-        
-        None // No complete JSON yet, continue accumulating
-    }
 
     /// Find Claude Code CLI binary
     #[instrument(level = "debug")]
@@ -280,9 +337,44 @@ impl SubprocessCLITransport {
         Err(ClaudeSDKError::CLINotFound(CLINotFoundError::new(error_msg)))
     }
 
-    /// Build CLI command with arguments
+    /// Set up the CLI's environment from the options
+    ///
+    /// Only the command is changed, never this process's own environment.
+    fn apply_env(&self, command: &mut Command) {
+        if self.options.env_clear {
+            command.env_clear();
+            let inherited: Vec<(String, String)> = std::env::vars()
+                .filter(|(name, _)| self.options.env_allowlist.iter().any(|pattern| env_name_matches(pattern, name)))
+                .collect();
+            debug!(
+                inherited = ?inherited.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+                "Starting CLI from a cleared environment"
+            );
+            command.envs(inherited);
+        }
+
+        command.env("CLAUDE_CODE_ENTRYPOINT", "sdk-rust");
+
+        for (name, value) in &self.options.env {
+            match value {
+                Some(value) => {
+                    debug!(name = %name, "Setting CLI environment variable");
+                    command.env(name, value);
+                }
+                None => {
+                    debug!(name = %name, "Removing CLI environment variable");
+                    command.env_remove(name);
+                }
+            }
+        }
+    }
+
+    /// Arguments the CLI is launched with, starting with the CLI path
+    ///
+    /// Options the SDK handles itself, such as `cwd`, `retry_policy` or
+    /// `max_budget_usd`, add no arguments.
     #[instrument(level = "trace", skip(self))]
-    fn build_command(&self) -> Vec<String> {
+    pub fn build_command(&self) -> Vec<String> {
         debug!("Building CLI command with arguments");
         let mut cmd = vec![
             self.cli_path.clone(),
@@ -301,12 +393,16 @@ impl SubprocessCLITransport {
             cmd.extend(["--append-system-prompt".to_string(), append_system_prompt.clone()]);
         }
 
-        if !self.options.allowed_tools.is_empty() {
-            debug!(allowed_tools = ?self.options.allowed_tools, "Adding allowed tools");
-            cmd.extend([
-                "--allowedTools".to_string(),
-                self.options.allowed_tools.join(","),
-            ]);
+        // MCP tools are allowed alongside the built-in ones
+        let mut allowed_tools = self.options.allowed_tools.clone();
+        for tool in &self.options.mcp_tools {
+            if !allowed_tools.contains(tool) {
+                allowed_tools.push(tool.clone());
+            }
+        }
+        if !allowed_tools.is_empty() {
+            debug!(allowed_tools = ?allowed_tools, "Adding allowed tools");
+            cmd.extend(["--allowedTools".to_string(), allowed_tools.join(",")]);
         }
 
         if let Some(max_turns) = self.options.max_turns {
@@ -314,6 +410,11 @@ impl SubprocessCLITransport {
             cmd.extend(["--max-turns".to_string(), max_turns.to_string()]);
         }
 
+        if let Some(max_thinking_tokens) = self.options.max_thinking_tokens {
+            debug!(max_thinking_tokens, "Adding thinking token limit");
+            cmd.extend(["--max-thinking-tokens".to_string(), max_thinking_tokens.to_string()]);
+        }
+
         if !self.options.disallowed_tools.is_empty() {
             debug!(disallowed_tools = ?self.options.disallowed_tools, "Adding disallowed tools");
             cmd.extend([
@@ -327,6 +428,17 @@ impl SubprocessCLITransport {
             cmd.extend(["--model".to_string(), model.clone()]);
         }
 
+        // The CLI takes a single fallback model; the SDK walks the rest of the chain
+        if let Some(fallback_model) = self
+            .options
+            .fallback_models
+            .iter()
+            .find(|fallback| Some(*fallback) != self.options.model.as_ref())
+        {
+            debug!(fallback_model = %fallback_model, "Adding fallback model");
+            cmd.extend(["--fallback-model".to_string(), fallback_model.clone()]);
+        }
+
         if let Some(permission_prompt_tool_name) = &self.options.permission_prompt_tool_name {
             debug!(tool_name = %permission_prompt_tool_name, "Adding permission prompt tool");
             cmd.extend([
@@ -355,21 +467,133 @@ impl SubprocessCLITransport {
             cmd.extend(["--resume".to_string(), resume.clone()]);
         }
 
+        if self.options.fork_session {
+            debug!("Adding fork session flag");
+            cmd.push("--fork-session".to_string());
+        }
+
         if !self.options.mcp_servers.is_empty() {
             debug!(mcp_servers_count = self.options.mcp_servers.len(), "Adding MCP servers configuration");
-            let mcp_config = serde_json::json!({
-                "mcpServers": self.options.mcp_servers
-            });
+            // Sorted so the same options always give the same command line
+            let servers: BTreeMap<_, _> = self.options.mcp_servers.iter().collect();
+            let mcp_config = serde_json::json!({ "mcpServers": servers });
             cmd.extend([
                 "--mcp-config".to_string(),
                 mcp_config.to_string(),
             ]);
         }
 
+        if self.options.strict_mcp_config {
+            debug!("Adding strict MCP config flag");
+            cmd.push("--strict-mcp-config".to_string());
+        }
+
+        for dir in &self.options.add_dirs {
+            debug!(dir = %dir.display(), "Adding additional directory");
+            cmd.extend(["--add-dir".to_string(), dir.to_string_lossy().into_owned()]);
+        }
+
+        if let Some(settings) = &self.options.settings {
+            let value = match settings {
+                Settings::File(path) => path.to_string_lossy().into_owned(),
+                Settings::Inline(value) => value.to_string(),
+            };
+            debug!(settings = %value, "Adding settings");
+            cmd.extend(["--settings".to_string(), value]);
+        }
+
+        if let Some(sources) = &self.options.setting_sources {
+            let sources: Vec<&str> = sources.iter().map(SettingSource::as_str).collect();
+            debug!(setting_sources = ?sources, "Adding setting sources");
+            cmd.extend(["--setting-sources".to_string(), sources.join(",")]);
+        }
+
+        for (flag, value) in &self.options.extra_args {
+            debug!(flag = %flag, value = ?value, "Adding extra argument");
+            cmd.push(format!("--{}", flag));
+            cmd.extend(value.clone());
+        }
+
         cmd.extend(["--print".to_string(), self.prompt.clone()]);
         debug!(total_args = cmd.len(), "Built complete CLI command");
         cmd
     }
+
+    /// Receive messages from CLI along with the bytes each was decoded from
+    #[instrument(level = "debug", skip(self))]
+    pub fn receive_decoded(&mut self) -> DecodedFrameStream<'_> {
+        if let Some(process) = &mut self.process {
+            if let Some(stdout) = process.stdout.take() {
+                debug!("Setting up message stream from subprocess stdout");
+                let mut stdout = stdout;
+                let mut chunk = vec![0u8; READ_CHUNK_SIZE];
+
+                // Raw bytes are framed incrementally, so each byte of output is
+                // scanned once no matter how a message is split across reads
+                let stream = async_stream::stream! {
+                    loop {
+                        match stdout.read(&mut chunk).await {
+                            Ok(0) => break,
+                            Ok(n) => {
+                                self.process_bytes(&chunk[..n]);
+                                while let Some(result) = self.next_decoded() {
+                                    yield result;
+                                }
+                            }
+                            Err(e) => {
+                                error!(error = %e, "Error reading from subprocess stdout");
+                                yield Err(ClaudeSDKError::Io(e));
+                                break;
+                            }
+                        }
+                    }
+
+                    // Output without a trailing newline is still framed on EOF
+                    self.process_bytes(b"\n");
+                    while let Some(result) = self.next_decoded() {
+                        yield result;
+                    }
+
+                    // Handle any remaining buffer content when stream ends
+                    if self.framer.has_partial_frame() {
+                        let partial = String::from_utf8_lossy(self.framer.partial_frame()).into_owned();
+                        warn!(
+                            buffer_length = partial.len(),
+                            buffer_preview = %self.safety_limits.safe_log_preview(&partial),
+                            "Stream ended with incomplete JSON buffer"
+                        );
+                        // Try to parse whatever we have as a final attempt
+                        if let Some(result) = self.try_decode_json_buffer() {
+                            yield result;
+                        } else {
+                            // If it still doesn't parse, it's malformed JSON
+                            let error = ClaudeSDKError::CLIJSONDecode(
+                                CLIJSONDecodeError::new(
+                                    partial,
+                                    serde_json::Error::io(std::io::Error::new(
+                                        std::io::ErrorKind::InvalidData,
+                                        "Incomplete JSON at end of stream"
+                                    ))
+                                )
+                            );
+                            yield Err(error);
+                            self.framer.discard_partial_frame();
+                        }
+                    }
+                };
+                
+                return Box::pin(stream);
+            } else {
+                warn!("No stdout available from subprocess");
+            }
+        } else {
+            warn!("No active subprocess to receive messages from");
+        }
+        
+        // Return empty stream if no process or stdout
+        debug!("Returning empty message stream");
+        Box::pin(tokio_stream::empty())
+    }
 }
 
 #[async_trait::async_trait]
@@ -391,14 +615,18 @@ impl Transport for SubprocessCLITransport {
             .args(&cmd_args[1..])
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .env("CLAUDE_CODE_ENTRYPOINT", "sdk-rust");
+            .stderr(Stdio::piped());
+        self.apply_env(&mut command);
 
         if let Some(cwd) = &self.cwd {
             debug!(cwd = %cwd.display(), "Setting working directory");
             command.current_dir(cwd);
         }
 
+        if let Some(user) = &self.options.user {
+            run_as_user(&mut command, user, &self.options.env)?;
+        }
+
         debug!("Spawning subprocess");
         let process = command.spawn().map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
@@ -421,6 +649,26 @@ impl Transport for SubprocessCLITransport {
         })?;
 
         info!(pid = process.id(), "Successfully started Claude CLI subprocess");
+        let mut process = process;
+        if let Some(stderr) = process.stderr.take() {
+            let output = Arc::clone(&self.stderr_output);
+            self.stderr_task = Some(tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    debug!(line = %line, "CLI stderr");
+                    let mut output = output.lock().unwrap_or_else(|e| e.into_inner());
+                    output.push_str(&line);
+                    output.push('\n');
+                    if output.len() > MAX_STDERR_CAPTURE {
+                        let mut cut = output.len() - MAX_STDERR_CAPTURE;
+                        while !output.is_char_boundary(cut) {
+                            cut += 1;
+                        }
+                        output.drain(..cut);
+                    }
+                }
+            }));
+        }
         self.process = Some(process);
         Ok(())
     }
@@ -468,69 +716,8 @@ impl Transport for SubprocessCLITransport {
 
     /// Receive messages from CLI
     #[instrument(level = "debug", skip(self))]
-    fn receive_messages(&mut self) -> Pin<Box<dyn Stream<Item = Result<HashMap<String, serde_json::Value>, ClaudeSDKError>> + Send + '_>> {
-        if let Some(process) = &mut self.process {
-            if let Some(stdout) = process.stdout.take() {
-                debug!("Setting up message stream from subprocess stdout");
-                let reader = BufReader::new(stdout);
-                let mut lines_stream = LinesStream::new(reader.lines());
-                
-                // We need to use a different approach for multiline JSON parsing
-                // Since we need mutable access to self for the buffer, we can't use map directly
-                let stream = async_stream::stream! {
-                    while let Some(line_result) = lines_stream.next().await {
-                        match line_result {
-                            Ok(line) => {
-                                if let Some(result) = self.process_line(line) {
-                                    yield result;
-                                }
-                                // If process_line returns None, we're still accumulating JSON
-                            }
-                            Err(e) => {
-                                error!(error = %e, "Error reading line from subprocess stdout");
-                                yield Err(ClaudeSDKError::Io(e));
-                            }
-                        }
-                    }
-                    
-                    // Handle any remaining buffer content when stream ends
-                    if !self.json_buffer.is_empty() {
-                        warn!(
-                            buffer_length = self.json_buffer.len(),
-                            buffer_preview = %self.safety_limits.safe_log_preview(&self.json_buffer),
-                            "Stream ended with incomplete JSON buffer"
-                        );
-                        // Try to parse whatever we have as a final attempt
-                        if let Some(result) = self.try_parse_json_buffer() {
-                            yield result;
-                        } else {
-                            // If it still doesn't parse, it's malformed JSON
-                            let error = ClaudeSDKError::CLIJSONDecode(
-                                CLIJSONDecodeError::new(
-                                    &self.json_buffer,
-                                    serde_json::Error::io(std::io::Error::new(
-                                        std::io::ErrorKind::InvalidData,
-                                        "Incomplete JSON at end of stream"
-                                    ))
-                                )
-                            );
-                            yield Err(error);
-                            self.json_buffer.clear();
-                        }
-                    }
-                };
-                
-                return Box::pin(stream);
-            } else {
-                warn!("No stdout available from subprocess");
-            }
-        } else {
-            warn!("No active subprocess to receive messages from");
-        }
-        
-        // Return empty stream if no process or stdout
-        debug!("Returning empty message stream");
-        Box::pin(tokio_stream::empty())
+    fn receive_messages(&mut self) -> RawMessageStream<'_> {
+        Box::pin(self.receive_decoded().map(|result| result.map(|frame| frame.data)))
     }
 
     /// Check if subprocess is running
@@ -547,4 +734,81 @@ impl Transport for SubprocessCLITransport {
         debug!(is_connected, "Checked connection status");
         is_connected
     }
-} 
\ No newline at end of file
+} 
+
+/// Whether an allowlist entry matches a variable name; a trailing `*` matches any suffix
+fn env_name_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+/// Make `command` run as `user`, a user name or numeric uid
+///
+/// `HOME`, `USER` and `LOGNAME` are taken from the user's passwd entry,
+/// unless `env` sets them explicitly.
+#[cfg(unix)]
+fn run_as_user(
+    command: &mut Command,
+    user: &str,
+    env: &BTreeMap<String, Option<String>>,
+) -> Result<(), ClaudeSDKError> {
+    let entry = lookup_user(user).ok_or_else(|| {
+        ClaudeSDKError::CLIConnection(CLIConnectionError::new(format!(
+            "Unknown user {:?}: no entry in /etc/passwd",
+            user
+        )))
+    })?;
+    debug!(user, uid = entry.uid, gid = entry.gid, "Running CLI as user");
+    command.uid(entry.uid).gid(entry.gid);
+    for (name, value) in [("HOME", &entry.home), ("USER", &entry.name), ("LOGNAME", &entry.name)] {
+        if !env.contains_key(name) {
+            command.env(name, value);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn run_as_user(
+    _command: &mut Command,
+    user: &str,
+    _env: &BTreeMap<String, Option<String>>,
+) -> Result<(), ClaudeSDKError> {
+    Err(ClaudeSDKError::CLIConnection(CLIConnectionError::new(format!(
+        "Cannot run the CLI as {:?}: switching users is only supported on Unix",
+        user
+    ))))
+}
+
+/// A user's entry in `/etc/passwd`
+#[cfg(unix)]
+struct PasswdEntry {
+    name: String,
+    uid: u32,
+    gid: u32,
+    home: String,
+}
+
+/// Look up a user by name or uid in `/etc/passwd`
+///
+/// Only the file itself is read: users that come from other NSS sources,
+/// such as LDAP or systemd-homed, are not found.
+#[cfg(unix)]
+fn lookup_user(user: &str) -> Option<PasswdEntry> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let fields: Vec<&str> = line.split(':').collect();
+        let (name, uid, gid, home) = (fields.first()?, fields.get(2)?, fields.get(3)?, fields.get(5)?);
+        if *name != user && *uid != user {
+            return None;
+        }
+        Some(PasswdEntry {
+            name: name.to_string(),
+            uid: uid.parse().ok()?,
+            gid: gid.parse().ok()?,
+            home: home.to_string(),
+        })
+    })
+}
@@ -1,11 +1,18 @@
 //! Type definitions for Claude SDK.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 
+use crate::config_dir::ConfigDir;
+use crate::errors::{classify_api_error, ClaudeSDKError, ResultError, API_ERROR_PREFIX};
+use crate::ledger::Ledger;
+use crate::mcp::McpServerConfig;
+use crate::pricing::{PricingTable, Usage};
+use crate::retry::RetryPolicy;
+
 /// Permission modes for tool execution
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum PermissionMode {
     #[serde(rename = "default")]
@@ -16,12 +23,37 @@ pub enum PermissionMode {
     BypassPermissions,
 }
 
-/// MCP server configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct McpServerConfig {
-    pub transport: Vec<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub env: Option<HashMap<String, serde_json::Value>>,
+/// Settings passed to the CLI with `--settings`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Settings {
+    /// Path to a settings JSON file
+    File(PathBuf),
+    /// Settings given inline
+    Inline(serde_json::Value),
+}
+
+/// Settings files the CLI can load
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingSource {
+    /// `~/.claude/settings.json`
+    User,
+    /// `.claude/settings.json` in the project
+    Project,
+    /// `.claude/settings.local.json` in the project
+    Local,
+}
+
+impl SettingSource {
+    /// The source as the CLI spells it
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SettingSource::User => "user",
+            SettingSource::Project => "project",
+            SettingSource::Local => "local",
+        }
+    }
 }
 
 /// Text content block
@@ -87,6 +119,12 @@ pub struct SystemMessage {
     pub data: HashMap<String, serde_json::Value>,
 }
 
+/// Subtype of the system message reporting CLI output that was not valid JSON
+///
+/// Its data holds the parser's `error` and the offending `line`; the stream
+/// carries on with the next message.
+pub const JSON_DECODE_ERROR_SUBTYPE: &str = "json_decode_error";
+
 /// Result message with cost and usage information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResultMessage {
@@ -102,6 +140,169 @@ pub struct ResultMessage {
     pub usage: Option<HashMap<String, serde_json::Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub result: Option<String>,
+    /// Model that produced the result, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Cost the SDK estimated from token usage when the CLI reported none
+    ///
+    /// Kept apart from `total_cost_usd`, which only ever holds the CLI's
+    /// own figure, and never serialized.
+    #[serde(skip)]
+    pub estimated_cost_usd: Option<f64>,
+}
+
+/// Subtype of the results the SDK makes up when the CLI gives none, such as
+/// when it exits with only stderr output
+pub(crate) const SDK_ERROR_SUBTYPE: &str = "error";
+
+/// Subtype of a result message
+///
+/// Serialized as the wire string, so unknown subtypes round-trip unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ResultSubtype {
+    /// The run completed normally
+    Success,
+    /// The run stopped after reaching `max_turns`
+    ErrorMaxTurns,
+    /// The run failed while executing
+    ErrorDuringExecution,
+    /// The run stopped after exceeding its cost budget
+    ErrorMaxBudgetUsd,
+    /// A subtype this SDK version does not know about
+    Unknown(String),
+}
+
+impl ResultSubtype {
+    /// The subtype as it appears on the wire
+    pub fn as_str(&self) -> &str {
+        match self {
+            ResultSubtype::Success => "success",
+            ResultSubtype::ErrorMaxTurns => "error_max_turns",
+            ResultSubtype::ErrorDuringExecution => "error_during_execution",
+            ResultSubtype::ErrorMaxBudgetUsd => "error_max_budget_usd",
+            ResultSubtype::Unknown(subtype) => subtype,
+        }
+    }
+}
+
+impl From<&str> for ResultSubtype {
+    fn from(subtype: &str) -> Self {
+        match subtype {
+            "success" => ResultSubtype::Success,
+            "error_max_turns" => ResultSubtype::ErrorMaxTurns,
+            "error_during_execution" => ResultSubtype::ErrorDuringExecution,
+            "error_max_budget_usd" => ResultSubtype::ErrorMaxBudgetUsd,
+            other => ResultSubtype::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for ResultSubtype {
+    fn from(subtype: String) -> Self {
+        ResultSubtype::from(subtype.as_str())
+    }
+}
+
+impl From<ResultSubtype> for String {
+    fn from(subtype: ResultSubtype) -> Self {
+        subtype.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for ResultSubtype {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ResultMessage {
+    /// Token counts from [`ResultMessage::usage`]
+    pub fn typed_usage(&self) -> Option<Usage> {
+        self.usage.as_ref().map(Usage::from_map)
+    }
+
+    /// Cost of the run in US dollars
+    ///
+    /// Uses `total_cost_usd` when the CLI reported it, then the SDK's
+    /// `estimated_cost_usd`, and otherwise prices the reported usage for
+    /// [`ResultMessage::model`] with `pricing`. Check
+    /// [`ResultMessage::cost_is_estimate`] to tell the cases apart.
+    pub fn cost_usd(&self, pricing: &PricingTable) -> Option<f64> {
+        self.total_cost_usd
+            .or(self.estimated_cost_usd)
+            .or_else(|| pricing.cost(self.model.as_deref()?, &self.typed_usage()?))
+    }
+
+    /// Whether [`ResultMessage::cost_usd`] is an estimate rather than a billed amount
+    pub fn cost_is_estimate(&self) -> bool {
+        self.total_cost_usd.is_none()
+    }
+
+    /// Typed view of [`ResultMessage::subtype`]
+    pub fn result_subtype(&self) -> ResultSubtype {
+        ResultSubtype::from(self.subtype.as_str())
+    }
+
+    /// Turn an error result into the matching [`ClaudeSDKError`]
+    ///
+    /// Successful results are returned unchanged. `error_max_turns` maps to
+    /// [`ClaudeSDKError::MaxTurnsReached`] and `error_max_budget_usd` to
+    /// [`ClaudeSDKError::BudgetExceeded`]. A failed `success` result whose
+    /// text starts with the CLI's `API Error:` prefix maps to the matching
+    /// API error variant, such as [`ClaudeSDKError::RateLimited`], as does an
+    /// `error` result the SDK made from the CLI's stderr. A subtype
+    /// this SDK does not know is only an error when `is_error` says so. Any
+    /// other error result is a [`ClaudeSDKError::Execution`]. The result text is
+    /// only classified in that one case, so an answer that merely mentions
+    /// a rate limit is not mistaken for one.
+    pub fn into_result(self) -> Result<ResultMessage, ClaudeSDKError> {
+        let subtype = self.result_subtype();
+        if matches!(subtype, ResultSubtype::Success | ResultSubtype::Unknown(_)) && !self.is_error {
+            return Ok(self);
+        }
+
+        if subtype == ResultSubtype::Success && self.is_error {
+            let api_error = self
+                .result
+                .as_deref()
+                .filter(|text| text.trim_start().starts_with(API_ERROR_PREFIX))
+                .and_then(classify_api_error);
+            if let Some(error) = api_error {
+                return Err(error.with_session_id(&self.session_id));
+            }
+        }
+
+        // The SDK's own results for runs that ended without one hold the
+        // CLI's stderr, where API failures show up as log text
+        if self.subtype == SDK_ERROR_SUBTYPE && self.is_error {
+            if let Some(error) = self.result.as_deref().and_then(classify_api_error) {
+                return Err(error.with_session_id(&self.session_id));
+            }
+        }
+
+        let error = match subtype {
+            ResultSubtype::ErrorMaxTurns => {
+                let message = format!("Reached maximum of {} turns", self.num_turns);
+                ClaudeSDKError::MaxTurnsReached(ResultError::new(message, self))
+            }
+            ResultSubtype::ErrorMaxBudgetUsd => {
+                let message = match self.total_cost_usd.or(self.estimated_cost_usd) {
+                    Some(cost) => format!("Run stopped after spending ${:.4}", cost),
+                    None => "Run stopped after exceeding its budget".to_string(),
+                };
+                ClaudeSDKError::BudgetExceeded(ResultError::new(message, self))
+            }
+            _ => {
+                let message = self
+                    .result
+                    .clone()
+                    .unwrap_or_else(|| format!("Run ended with {}", subtype));
+                ClaudeSDKError::Execution(ResultError::new(message, self))
+            }
+        };
+        Err(error)
+    }
 }
 
 /// Message types
@@ -118,44 +319,175 @@ pub enum Message {
     Result(ResultMessage),
 }
 
+impl Message {
+    /// Convert into the CLI's `stream-json` envelope
+    ///
+    /// User and assistant messages are wrapped as `{"type": ..., "message":
+    /// {"role": ..., "content": ...}}`, while system and result messages stay
+    /// flat. The output is what [`Message::from_wire`] reads, so the two
+    /// round-trip exactly; CLI fields the SDK does not model are not kept,
+    /// except on system messages, whose `data` holds the whole envelope.
+    pub fn to_wire(&self) -> serde_json::Value {
+        match self {
+            Message::User(msg) => serde_json::json!({
+                "type": "user",
+                "message": {
+                    "role": "user",
+                    "content": msg.content,
+                },
+            }),
+            Message::Assistant(msg) => serde_json::json!({
+                "type": "assistant",
+                "message": {
+                    "role": "assistant",
+                    "content": msg.content,
+                },
+            }),
+            Message::System(msg) => {
+                let mut data: serde_json::Map<String, serde_json::Value> = msg
+                    .data
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                data.insert("type".to_string(), "system".into());
+                data.insert("subtype".to_string(), msg.subtype.clone().into());
+                serde_json::Value::Object(data)
+            }
+            Message::Result(_) => {
+                serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+            }
+        }
+    }
+
+    /// Parse a message from the CLI's `stream-json` envelope
+    ///
+    /// Returns `None` for message types the SDK does not model or when
+    /// required fields are missing.
+    pub fn from_wire(value: &serde_json::Value) -> Option<Message> {
+        let data: HashMap<String, serde_json::Value> = value
+            .as_object()?
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        crate::parse_message(data)
+    }
+}
+
+/// A message exactly as emitted by the CLI, together with its typed form
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawMessage {
+    /// The JSON text of the message as the CLI wrote it, or `null` for
+    /// messages the SDK produced itself
+    pub raw: Box<serde_json::value::RawValue>,
+    /// The typed message, if the SDK models this message type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<Message>,
+}
+
+impl RawMessage {
+    /// Wrap a message the SDK produced itself, such as a synthesized error result
+    ///
+    /// The CLI never emitted it, so `raw` is `null`.
+    pub fn from_message(message: Message) -> Self {
+        Self {
+            raw: serde_json::value::RawValue::NULL.to_owned(),
+            message: Some(message),
+        }
+    }
+
+    /// Decode `raw` into a JSON value, e.g. to read fields the typed message drops
+    pub fn value(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::from_str(self.raw.get())
+    }
+}
+
+/// Variables the CLI typically needs when started with `env_clear`
+///
+/// Covers the executable search path, home directory, locale, terminal and
+/// temp dir, plus `ANTHROPIC_*` and `CLAUDE_*` for authentication and
+/// configuration.
+pub const BASE_ENV_ALLOWLIST: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TMPDIR",
+    "TERM",
+    "LANG",
+    "LC_*",
+    "ANTHROPIC_*",
+    "CLAUDE_*",
+];
+
 /// Query options for Claude SDK
-#[derive(Debug, Clone)]
+///
+/// Deserializing fills missing fields from the defaults and rejects unknown
+/// ones; see [`crate::profile::Profile`] for loading options from files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ClaudeCodeOptions {
     pub allowed_tools: Vec<String>,
-    pub max_thinking_tokens: u32,
+    /// Thinking token limit passed to the CLI (the CLI's default when `None`)
+    pub max_thinking_tokens: Option<u32>,
     pub system_prompt: Option<String>,
     pub append_system_prompt: Option<String>,
+    /// MCP tools Claude may use without asking, added to `allowed_tools`
     pub mcp_tools: Vec<String>,
+    /// MCP servers passed with `--mcp-config`, by name
     pub mcp_servers: HashMap<String, McpServerConfig>,
     pub permission_mode: Option<PermissionMode>,
     pub continue_conversation: bool,
     pub resume: Option<String>,
+    /// Resume into a new session id instead of appending to the resumed session
+    pub fork_session: bool,
     pub max_turns: Option<u32>,
     pub disallowed_tools: Vec<String>,
     pub model: Option<String>,
     pub permission_prompt_tool_name: Option<String>,
     pub cwd: Option<PathBuf>,
-}
-
-impl Default for ClaudeCodeOptions {
-    fn default() -> Self {
-        Self {
-            allowed_tools: Vec::new(),
-            max_thinking_tokens: 8000,
-            system_prompt: None,
-            append_system_prompt: None,
-            mcp_tools: Vec::new(),
-            mcp_servers: HashMap::new(),
-            permission_mode: None,
-            continue_conversation: false,
-            resume: None,
-            max_turns: None,
-            disallowed_tools: Vec::new(),
-            model: None,
-            permission_prompt_tool_name: None,
-            cwd: None,
-        }
-    }
+    /// Claude CLI executable to run; looked up on `PATH` and in the usual install locations when `None`
+    pub cli_path: Option<PathBuf>,
+    /// Directories Claude may access besides `cwd`
+    pub add_dirs: Vec<PathBuf>,
+    /// Settings to apply on top of the settings files
+    pub settings: Option<Settings>,
+    /// Settings files the CLI loads (the CLI's default when `None`)
+    pub setting_sources: Option<Vec<SettingSource>>,
+    /// Use only the MCP servers in `mcp_servers`, ignoring other MCP configuration
+    pub strict_mcp_config: bool,
+    /// Run the CLI as this user, given as a name or uid (Unix only)
+    ///
+    /// The user must have an entry in `/etc/passwd`, which supplies its
+    /// primary group and the CLI's `HOME`, `USER` and `LOGNAME` unless `env`
+    /// sets them. Users known only through other NSS sources are not found.
+    pub user: Option<String>,
+    /// Config directory for the CLI instead of the user's `~/.claude`
+    pub config_dir: Option<ConfigDir>,
+    /// Environment variables to set (`Some`) or remove (`None`) for the CLI
+    pub env: BTreeMap<String, Option<String>>,
+    /// Start the CLI from an empty environment instead of inheriting this process's
+    pub env_clear: bool,
+    /// Variables still inherited when `env_clear` is set
+    ///
+    /// A trailing `*` matches any suffix, as in `LC_*`. See
+    /// [`BASE_ENV_ALLOWLIST`] for what the CLI usually needs.
+    pub env_allowlist: Vec<String>,
+    /// Further CLI flags, named without leading dashes, with an optional value
+    ///
+    /// Appended after every typed option, for flags the SDK does not cover yet.
+    pub extra_args: BTreeMap<String, Option<String>>,
+    /// Models to fall back to, in order, when the current model is
+    /// overloaded or unavailable
+    pub fallback_models: Vec<String>,
+    /// Re-launch the CLI after transient failures (no retries when `None`)
+    pub retry_policy: Option<RetryPolicy>,
+    /// Interrupt the query once its estimated cost exceeds this many US dollars
+    pub max_budget_usd: Option<f64>,
+    /// Prices used to estimate cost (built-in table when `None`)
+    pub pricing: Option<PricingTable>,
+    /// Record each completed query's cost and usage in this ledger
+    pub ledger: Option<Ledger>,
 }
 
 impl ClaudeCodeOptions {
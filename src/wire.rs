@@ -0,0 +1,282 @@
+//! Borrowed wire model for the CLI's `stream-json` output.
+//!
+//! These types deserialize directly from a line of CLI output without
+//! building an intermediate map. Strings borrow from the line whenever they
+//! contain no escapes, and tool inputs, tool results and usage stay as
+//! [`RawValue`] slices until they are converted into owned [`Message`]s.
+//!
+//! Fields shared by several message types, such as `message` or
+//! `duration_ms`, are kept raw in [`WireMessage`] and only decoded for the
+//! types that define them, so an unexpected shape in one message type never
+//! fails the line.
+//!
+//! The model is standalone: the query stream decodes each message through a
+//! map and [`crate::parse_message`], because it inspects fields the wire
+//! model does not keep. Use [`parse_message_line`] for recorded output.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use tracing::{debug, instrument, warn};
+
+use crate::types::*;
+
+/// Top-level envelope of one CLI message
+#[derive(Debug, Deserialize)]
+pub struct WireMessage<'a> {
+    #[serde(rename = "type", default, borrow, deserialize_with = "borrowed_str")]
+    pub message_type: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_str")]
+    pub subtype: Option<Cow<'a, str>>,
+    /// The `message` object of user and assistant messages, see [`Self::body`]
+    #[serde(default, borrow)]
+    pub message: Option<&'a RawValue>,
+    #[serde(default, borrow)]
+    pub duration_ms: Option<&'a RawValue>,
+    #[serde(default, borrow)]
+    pub duration_api_ms: Option<&'a RawValue>,
+    #[serde(default, borrow)]
+    pub is_error: Option<&'a RawValue>,
+    #[serde(default, borrow)]
+    pub num_turns: Option<&'a RawValue>,
+    #[serde(default, borrow, deserialize_with = "borrowed_str")]
+    pub session_id: Option<Cow<'a, str>>,
+    #[serde(default, borrow)]
+    pub total_cost_usd: Option<&'a RawValue>,
+    #[serde(default, borrow)]
+    pub usage: Option<&'a RawValue>,
+    #[serde(default, borrow)]
+    pub result: Option<&'a RawValue>,
+    #[serde(default, borrow, deserialize_with = "borrowed_str")]
+    pub model: Option<Cow<'a, str>>,
+}
+
+/// The `message` object of user and assistant messages
+#[derive(Debug, Deserialize)]
+pub struct WireBody<'a> {
+    #[serde(default, borrow)]
+    pub content: Option<WireContent<'a>>,
+}
+
+/// Message content: plain text for user prompts, blocks for assistant turns
+#[derive(Debug)]
+pub enum WireContent<'a> {
+    Text(Cow<'a, str>),
+    Blocks(Vec<WireBlock<'a>>),
+    /// Any other JSON value, which the SDK does not model
+    Other,
+}
+
+/// One content block, with the fields of every block type flattened
+#[derive(Debug, Deserialize)]
+pub struct WireBlock<'a> {
+    #[serde(rename = "type", default, borrow, deserialize_with = "borrowed_str")]
+    pub block_type: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_str")]
+    pub text: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_str")]
+    pub id: Option<Cow<'a, str>>,
+    #[serde(default, borrow, deserialize_with = "borrowed_str")]
+    pub name: Option<Cow<'a, str>>,
+    #[serde(default, borrow)]
+    pub input: Option<&'a RawValue>,
+    #[serde(default, borrow, deserialize_with = "borrowed_str")]
+    pub tool_use_id: Option<Cow<'a, str>>,
+    #[serde(default, borrow)]
+    pub content: Option<&'a RawValue>,
+    #[serde(default)]
+    pub is_error: Option<bool>,
+}
+
+/// Deserialize an optional string, borrowing from the input when it has no escapes
+///
+/// Values of any other type read as absent rather than failing the message.
+fn borrowed_str<'de: 'a, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Cow<'a, str>>, D::Error> {
+    #[derive(Deserialize)]
+    struct Borrowed<'a>(#[serde(borrow)] Cow<'a, str>);
+
+    let raw = Option::<&'a RawValue>::deserialize(deserializer)?;
+    Ok(raw.and_then(decode::<Borrowed<'a>>).map(|borrowed| borrowed.0))
+}
+
+/// Decode a raw field, treating a value of the wrong type as absent
+fn decode<'a, T: Deserialize<'a>>(raw: &'a RawValue) -> Option<T> {
+    serde_json::from_str(raw.get()).ok()
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for WireContent<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ContentVisitor<'a>(std::marker::PhantomData<&'a ()>);
+
+        impl<'de: 'a, 'a> Visitor<'de> for ContentVisitor<'a> {
+            type Value = WireContent<'a>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string or an array of content blocks")
+            }
+
+            fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+                Ok(WireContent::Text(Cow::Borrowed(v)))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(WireContent::Text(Cow::Owned(v.to_string())))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(WireContent::Text(Cow::Owned(v)))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut blocks = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+                while let Some(block) = seq.next_element()? {
+                    blocks.push(block);
+                }
+                Ok(WireContent::Blocks(blocks))
+            }
+
+            fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+                de::IgnoredAny::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                Ok(WireContent::Other)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(WireContent::Other)
+            }
+
+            fn visit_bool<E: de::Error>(self, _: bool) -> Result<Self::Value, E> {
+                Ok(WireContent::Other)
+            }
+
+            fn visit_i64<E: de::Error>(self, _: i64) -> Result<Self::Value, E> {
+                Ok(WireContent::Other)
+            }
+
+            fn visit_u64<E: de::Error>(self, _: u64) -> Result<Self::Value, E> {
+                Ok(WireContent::Other)
+            }
+
+            fn visit_f64<E: de::Error>(self, _: f64) -> Result<Self::Value, E> {
+                Ok(WireContent::Other)
+            }
+        }
+
+        deserializer.deserialize_any(ContentVisitor(std::marker::PhantomData))
+    }
+}
+
+impl<'a> WireMessage<'a> {
+    /// Deserialize one line of CLI output, borrowing from it where possible
+    pub fn from_line(line: &'a str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(line)
+    }
+
+    /// Decode the `message` object, if it has the shape of a user or assistant message
+    pub fn body(&self) -> Option<WireBody<'a>> {
+        decode(self.message?)
+    }
+
+    /// Convert into an owned [`Message`]
+    ///
+    /// System messages carry every field of the envelope, so `line` is parsed
+    /// again for them; other message types only convert what they model.
+    /// Returns `None` for unknown message types or missing required fields.
+    pub fn into_message(self, line: &str) -> Option<Message> {
+        match self.message_type.as_deref()? {
+            "user" => match self.body()?.content? {
+                WireContent::Text(text) => Some(Message::User(UserMessage {
+                    content: text.into_owned(),
+                })),
+                _ => None,
+            },
+            "assistant" => {
+                let blocks = match self.body()?.content? {
+                    WireContent::Blocks(blocks) => blocks,
+                    _ => return None,
+                };
+                let mut content = Vec::with_capacity(blocks.len());
+                for block in blocks {
+                    if let Some(block) = block.into_content_block()? {
+                        content.push(block);
+                    }
+                }
+                Some(Message::Assistant(AssistantMessage { content }))
+            }
+            "system" => {
+                let subtype = self.subtype?.into_owned();
+                let data = serde_json::from_str(line).ok()?;
+                Some(Message::System(SystemMessage { subtype, data }))
+            }
+            "result" => Some(Message::Result(ResultMessage {
+                subtype: self.subtype?.into_owned(),
+                duration_ms: decode(self.duration_ms?)?,
+                duration_api_ms: decode(self.duration_api_ms?)?,
+                is_error: decode(self.is_error?)?,
+                num_turns: decode::<u64>(self.num_turns?)? as u32,
+                session_id: self.session_id?.into_owned(),
+                total_cost_usd: self.total_cost_usd.and_then(decode),
+                usage: self.usage.and_then(decode),
+                result: self.result.and_then(decode),
+                model: self.model.map(Cow::into_owned),
+                estimated_cost_usd: None,
+            })),
+            other => {
+                warn!(message_type = other, "Unknown message type");
+                None
+            }
+        }
+    }
+}
+
+impl WireBlock<'_> {
+    /// Convert into an owned [`ContentBlock`]
+    ///
+    /// Returns `None` when a required field is missing and `Some(None)` for
+    /// block types the SDK does not model.
+    fn into_content_block(self) -> Option<Option<ContentBlock>> {
+        let block = match self.block_type.as_deref()? {
+            "text" => ContentBlock::Text(TextBlock {
+                text: self.text?.into_owned(),
+            }),
+            "tool_use" => ContentBlock::ToolUse(ToolUseBlock {
+                id: self.id?.into_owned(),
+                name: self.name?.into_owned(),
+                input: serde_json::from_str(self.input?.get()).ok()?,
+            }),
+            "tool_result" => ContentBlock::ToolResult(ToolResultBlock {
+                tool_use_id: self.tool_use_id?.into_owned(),
+                content: self.content.and_then(parse_tool_result_content),
+                is_error: self.is_error,
+            }),
+            _ => return Some(None),
+        };
+        Some(Some(block))
+    }
+}
+
+fn parse_tool_result_content(raw: &RawValue) -> Option<ToolResultContent> {
+    let json = raw.get();
+    match json.as_bytes().first()? {
+        b'"' => serde_json::from_str(json).ok().map(ToolResultContent::Text),
+        b'[' => serde_json::from_str::<Vec<HashMap<String, serde_json::Value>>>(json)
+            .ok()
+            .map(ToolResultContent::Structured),
+        _ => None,
+    }
+}
+
+/// Parse one line of CLI output straight into a [`Message`]
+///
+/// This is the allocation-light counterpart of decoding the line into a map
+/// and calling [`crate::parse_message`]. Returns `Ok(None)` for message types
+/// the SDK does not model, and an error if the line is not valid JSON.
+#[instrument(level = "trace", skip(line), fields(line_length = line.len()))]
+pub fn parse_message_line(line: &str) -> Result<Option<Message>, serde_json::Error> {
+    let wire = WireMessage::from_line(line)?;
+    debug!(message_type = ?wire.message_type, "Parsing wire message");
+    Ok(wire.into_message(line))
+}
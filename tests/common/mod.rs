@@ -0,0 +1,51 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use tempfile::TempDir;
+
+/// Create an empty directory under the system temp dir
+///
+/// `name` must be unique across all test binaries, since they run in
+/// parallel. Anything left from an earlier run is removed first.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("claude-sdk-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write an executable shell script standing in for the Claude CLI
+///
+/// The script runs with `$dir` set to the returned directory and `$n` to
+/// the number of this run, starting at 1; the arguments of each run are
+/// saved to `$dir/args_$n`. The directory is removed when the `TempDir`
+/// is dropped. Pass the script's path as `cli_path` rather than changing
+/// `PATH`, so tests can run in parallel.
+#[cfg(unix)]
+pub fn fake_cli(script: &str) -> (TempDir, PathBuf) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::Builder::new().prefix("claude-sdk-test-").tempdir().unwrap();
+    let path = dir.path().join("claude");
+    std::fs::write(
+        &path,
+        format!(
+            r#"#!/bin/sh
+dir='{dir}'
+n=$(cat "$dir/count" 2>/dev/null || echo 0)
+n=$((n + 1))
+echo $n > "$dir/count"
+echo "$@" > "$dir/args_$n"
+{script}
+"#,
+            dir = dir.path().display(),
+            script = script
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    (dir, path)
+}
@@ -0,0 +1,145 @@
+//! Tests for building and validating options.
+
+use claude_code_sdk::{ClaudeCodeOptions, ClaudeSDKError, PermissionMode, RetryPolicy};
+
+#[test]
+fn test_builder_sets_fields() {
+    let cwd = std::env::temp_dir();
+    let options = ClaudeCodeOptions::builder()
+        .allowed_tools(["Read", "Grep"])
+        .allow_tool("Edit")
+        .disallow_tool("Bash")
+        .system_prompt("You are terse")
+        .append_system_prompt("Answer in English")
+        .permission_mode(PermissionMode::AcceptEdits)
+        .resume("3f1c")
+        .fork_session(true)
+        .max_turns(5)
+        .max_thinking_tokens(4000)
+        .model("opus")
+        .fallback_model("sonnet")
+        .fallback_model("haiku")
+        .max_budget_usd(2.5)
+        .cwd(&cwd)
+        .build()
+        .unwrap();
+
+    assert_eq!(options.allowed_tools, vec!["Read", "Grep", "Edit"]);
+    assert_eq!(options.disallowed_tools, vec!["Bash"]);
+    assert_eq!(options.system_prompt.as_deref(), Some("You are terse"));
+    assert_eq!(options.append_system_prompt.as_deref(), Some("Answer in English"));
+    assert_eq!(options.permission_mode, Some(PermissionMode::AcceptEdits));
+    assert_eq!(options.resume.as_deref(), Some("3f1c"));
+    assert!(options.fork_session);
+    assert_eq!(options.max_turns, Some(5));
+    assert_eq!(options.max_thinking_tokens, Some(4000));
+    assert_eq!(options.model.as_deref(), Some("opus"));
+    assert_eq!(options.fallback_models, vec!["sonnet", "haiku"]);
+    assert_eq!(options.max_budget_usd, Some(2.5));
+    assert_eq!(options.cwd, Some(cwd));
+
+    // Unset fields keep their defaults
+    assert!(!options.continue_conversation);
+    assert!(options.retry_policy.is_none());
+}
+
+#[test]
+fn test_builder_rejects_conflicts() {
+    let error = ClaudeCodeOptions::builder()
+        .continue_conversation(true)
+        .resume("3f1c")
+        .allowed_tools(["Read", "Bash", "Bash"])
+        .disallowed_tools(["Bash", "WebFetch"])
+        .cwd("/definitely/not/a/real/dir")
+        .max_turns(0)
+        .max_budget_usd(-1.0)
+        .retry_policy(RetryPolicy {
+            max_attempts: 0,
+            ..RetryPolicy::default()
+        })
+        .build()
+        .unwrap_err();
+
+    assert_eq!(
+        error.problems,
+        vec![
+            "continue_conversation and resume cannot both be set",
+            "tools both allowed and disallowed: Bash",
+            "cwd /definitely/not/a/real/dir is not an existing directory",
+            "max_turns must be at least 1",
+            "max_budget_usd must be a positive amount, got -1",
+            "retry_policy.max_attempts must be at least 1",
+        ]
+    );
+
+    let error: ClaudeSDKError = error.into();
+    assert!(matches!(error, ClaudeSDKError::Validation(_)));
+    assert!(error.to_string().starts_with("Invalid options: continue_conversation and resume"));
+}
+
+#[test]
+fn test_validate_existing_options() {
+    assert!(ClaudeCodeOptions::default().validate().is_ok());
+
+    let options = ClaudeCodeOptions {
+        fork_session: true,
+        ..ClaudeCodeOptions::default()
+    };
+    let error = options.validate().unwrap_err();
+    assert_eq!(error.problems, vec!["fork_session requires resume or continue_conversation"]);
+
+    // The builder can start from existing options
+    let options = claude_code_sdk::ClaudeCodeOptionsBuilder::from_options(options)
+        .continue_conversation(true)
+        .build()
+        .unwrap();
+    assert!(options.fork_session && options.continue_conversation);
+}
+
+#[test]
+fn test_builder_checks_cli_flag_options() {
+    let options = ClaudeCodeOptions::builder()
+        .add_dir(std::env::temp_dir())
+        .settings(claude_code_sdk::Settings::Inline(serde_json::json!({"model": "opus"})))
+        .setting_sources([claude_code_sdk::SettingSource::Project])
+        .strict_mcp_config(true)
+        .extra_arg("debug", None)
+        .build()
+        .unwrap();
+    assert_eq!(options.add_dirs, vec![std::env::temp_dir()]);
+    assert!(options.strict_mcp_config);
+    assert_eq!(options.extra_args.get("debug"), Some(&None));
+
+    let error = ClaudeCodeOptions::builder()
+        .cli_path("/definitely/not/claude")
+        .add_dir("/definitely/not/a/real/dir")
+        .settings(claude_code_sdk::Settings::File("/definitely/not/settings.json".into()))
+        .extra_arg("--debug", None)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error.problems,
+        vec![
+            "cli_path /definitely/not/claude is not an existing file",
+            "add_dirs entry /definitely/not/a/real/dir is not an existing directory",
+            "settings file /definitely/not/settings.json does not exist",
+            "extra_args flag \"--debug\" must be named without leading dashes",
+        ]
+    );
+}
+
+#[test]
+fn test_builder_rejects_user_with_temporary_config_dir() {
+    let error = ClaudeCodeOptions::builder()
+        .user("builder")
+        .config_dir(claude_code_sdk::ConfigDir::temporary())
+        .build()
+        .unwrap_err();
+    assert_eq!(error.problems, vec!["config_dir must have a path when user is set"]);
+
+    assert!(ClaudeCodeOptions::builder()
+        .user("builder")
+        .config_dir(claude_code_sdk::ConfigDir::at(std::env::temp_dir().join("claude-builder-config")))
+        .build()
+        .is_ok());
+}
@@ -1,8 +1,10 @@
 //! Tests for Claude SDK client functionality.
 
-use claude_code_sdk::{query, AssistantMessage, ClaudeCodeOptions, ContentBlock, Message, TextBlock};
+use claude_code_sdk::{query, query_raw, AssistantMessage, ClaudeCodeOptions, ContentBlock, Message, RawMessage, TextBlock};
 use std::path::PathBuf;
 
+mod common;
+
 #[tokio::test]
 async fn test_query_basic() {
     // This is a basic test that would require mocking in a real scenario
@@ -20,7 +22,7 @@ async fn test_query_basic() {
 #[test]
 fn test_claude_code_options_default() {
     let options = ClaudeCodeOptions::default();
-    assert_eq!(options.max_thinking_tokens, 8000);
+    assert_eq!(options.max_thinking_tokens, None);
     assert!(options.allowed_tools.is_empty());
     assert!(options.system_prompt.is_none());
 }
@@ -28,7 +30,7 @@ fn test_claude_code_options_default() {
 #[test]
 fn test_claude_code_options_new() {
     let options = ClaudeCodeOptions::new();
-    assert_eq!(options.max_thinking_tokens, 8000);
+    assert_eq!(options.max_thinking_tokens, None);
     assert!(options.allowed_tools.is_empty());
 }
 
@@ -76,4 +78,67 @@ fn test_message_types() {
         }
         _ => panic!("Expected assistant message"),
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_query_raw_keeps_unknown_fields() {
+    use tokio_stream::StreamExt;
+
+    let (_dir, cli) = common::fake_cli(
+        r#"echo '{"type": "assistant", "message": {"id": "msg_1", "content": [{"type": "text", "text": "Hi"}], "stop_reason": "end_turn"}, "parent_tool_use_id": null}'
+echo '{"type": "rate_limit_event", "retry_in_ms": 250}'
+echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 20, "duration_api_ms": 10, "num_turns": 1, "session_id": "s1", "total_cost_usd": 0.01, "result": "Hi"}'"#,
+    );
+    let options = ClaudeCodeOptions {
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let messages: Vec<RawMessage> = query_raw("Hello", Some(options)).await.unwrap().collect().await;
+    assert_eq!(messages.len(), 3);
+
+    // Fields the typed message drops are still in the raw JSON
+    let raw = messages[0].value().unwrap();
+    assert_eq!(raw["message"]["stop_reason"], "end_turn");
+    assert!(raw.as_object().unwrap().contains_key("parent_tool_use_id"));
+    assert!(matches!(messages[0].message, Some(Message::Assistant(_))));
+
+    // Message types the SDK does not model come through untyped
+    assert_eq!(messages[1].value().unwrap()["retry_in_ms"], 250);
+    assert!(messages[1].message.is_none());
+
+    assert!(matches!(messages[2].message, Some(Message::Result(_))));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_query_raw_keeps_key_order() {
+    use tokio_stream::StreamExt;
+
+    let lines = [
+        r#"{"type":"system","subtype":"init","session_id":"s1","model":"claude-sonnet-4-5","cwd":"/work","tools":["Read"]}"#,
+        r#"{"type":"assistant","message":{"usage":{"output_tokens":2,"input_tokens":5},"id":"msg_1","content":[{"type":"text","text":"Hi"}]},"parent_tool_use_id":null}"#,
+        r#"{"type":"result","subtype":"success","session_id":"s1","is_error":false,"num_turns":1,"duration_ms":20,"duration_api_ms":10,"total_cost_usd":0.01,"result":"Hi"}"#,
+    ];
+    let script: Vec<String> = lines.iter().map(|line| format!("echo '{}'", line)).collect();
+    let (_dir, cli) = common::fake_cli(&script.join("\n"));
+    let options = ClaudeCodeOptions {
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let messages: Vec<RawMessage> = query_raw("Hello", Some(options)).await.unwrap().collect().await;
+
+    let raw: Vec<String> = messages.iter().map(|message| message.raw.to_string()).collect();
+    assert_eq!(raw, lines);
+}
+
+#[test]
+fn test_raw_message_from_message() {
+    let raw = RawMessage::from_message(Message::User(claude_code_sdk::UserMessage {
+        content: "Hello".to_string(),
+    }));
+
+    assert_eq!(raw.raw.get(), "null");
+    assert!(raw.value().unwrap().is_null());
+    assert!(matches!(raw.message, Some(Message::User(_))));
+}
@@ -0,0 +1,191 @@
+//! Table-driven tests for the CLI arguments built from each option.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use claude_code_sdk::transport::subprocess_cli::SubprocessCLITransport;
+use claude_code_sdk::{
+    ClaudeCodeOptions, Ledger, McpServerConfig, PermissionMode, PricingTable, RetryPolicy, SettingSource, Settings,
+};
+
+type Case = (&'static str, fn(&mut ClaudeCodeOptions), &'static [&'static str]);
+
+/// One row per option field: how to set it and the arguments it must add
+const CASES: &[Case] = &[
+    ("allowed_tools", |o| o.allowed_tools = vec!["Read".into(), "Edit".into()], &["--allowedTools", "Read,Edit"]),
+    ("max_thinking_tokens", |o| o.max_thinking_tokens = Some(4000), &["--max-thinking-tokens", "4000"]),
+    ("system_prompt", |o| o.system_prompt = Some("Be terse".into()), &["--system-prompt", "Be terse"]),
+    (
+        "append_system_prompt",
+        |o| o.append_system_prompt = Some("Use British spelling".into()),
+        &["--append-system-prompt", "Use British spelling"],
+    ),
+    (
+        "mcp_tools",
+        |o| o.mcp_tools = vec!["mcp__github__search".into()],
+        &["--allowedTools", "mcp__github__search"],
+    ),
+    (
+        "mcp_servers",
+        |o| {
+            o.mcp_servers = HashMap::from([(
+                "github".to_string(),
+                McpServerConfig::stdio("gh-mcp", ["--stdio"]).with_env("GITHUB_TOKEN", "ghp_example"),
+            )])
+        },
+        &["--mcp-config", r#"{"mcpServers":{"github":{"args":["--stdio"],"command":"gh-mcp","env":{"GITHUB_TOKEN":"ghp_example"},"type":"stdio"}}}"#],
+    ),
+    (
+        "permission_mode",
+        |o| o.permission_mode = Some(PermissionMode::AcceptEdits),
+        &["--permission-mode", "acceptEdits"],
+    ),
+    ("continue_conversation", |o| o.continue_conversation = true, &["--continue"]),
+    ("resume", |o| o.resume = Some("3f1c".into()), &["--resume", "3f1c"]),
+    (
+        "fork_session",
+        |o| {
+            o.resume = Some("3f1c".into());
+            o.fork_session = true
+        },
+        &["--resume", "3f1c", "--fork-session"],
+    ),
+    ("max_turns", |o| o.max_turns = Some(3), &["--max-turns", "3"]),
+    ("disallowed_tools", |o| o.disallowed_tools = vec!["Bash".into()], &["--disallowedTools", "Bash"]),
+    ("model", |o| o.model = Some("opus".into()), &["--model", "opus"]),
+    (
+        "permission_prompt_tool_name",
+        |o| o.permission_prompt_tool_name = Some("mcp__auth__approve".into()),
+        &["--permission-prompt-tool", "mcp__auth__approve"],
+    ),
+    (
+        "fallback_models",
+        |o| o.fallback_models = vec!["sonnet".into(), "haiku".into()],
+        &["--fallback-model", "sonnet"],
+    ),
+    ("strict_mcp_config", |o| o.strict_mcp_config = true, &["--strict-mcp-config"]),
+    (
+        "add_dirs",
+        |o| o.add_dirs = vec![PathBuf::from("/work/shared"), PathBuf::from("/work/docs")],
+        &["--add-dir", "/work/shared", "--add-dir", "/work/docs"],
+    ),
+    (
+        "settings",
+        |o| o.settings = Some(Settings::Inline(serde_json::json!({"model": "opus"}))),
+        &["--settings", r#"{"model":"opus"}"#],
+    ),
+    (
+        "setting_sources",
+        |o| o.setting_sources = Some(vec![SettingSource::Project, SettingSource::Local]),
+        &["--setting-sources", "project,local"],
+    ),
+    (
+        "extra_args",
+        |o| {
+            o.extra_args = BTreeMap::from([
+                ("debug".to_string(), None),
+                ("betas".to_string(), Some("context-1m".to_string())),
+            ])
+        },
+        &["--betas", "context-1m", "--debug"],
+    ),
+    // Options the SDK applies itself
+    ("user", |o| o.user = Some("builder".into()), &[]),
+    ("env", |o| o.env = BTreeMap::from([("FOO".to_string(), Some("bar".to_string()))]), &[]),
+    ("config_dir", |o| o.config_dir = Some(claude_code_sdk::ConfigDir::temporary()), &[]),
+    ("env_clear", |o| o.env_clear = true, &[]),
+    ("env_allowlist", |o| o.env_allowlist = vec!["PATH".into()], &[]),
+    ("cwd", |o| o.cwd = Some(PathBuf::from("/work/api")), &[]),
+    // The path given to the transport wins; see test_cli_path_from_options
+    ("cli_path", |o| o.cli_path = Some(PathBuf::from("/opt/claude/bin/claude")), &[]),
+    ("retry_policy", |o| o.retry_policy = Some(RetryPolicy::default()), &[]),
+    ("max_budget_usd", |o| o.max_budget_usd = Some(1.0), &[]),
+    ("pricing", |o| o.pricing = Some(PricingTable::default()), &[]),
+    ("ledger", |o| o.ledger = Some(Ledger::new("/tmp/ledger.jsonl")), &[]),
+];
+
+fn argv(options: ClaudeCodeOptions) -> Vec<String> {
+    SubprocessCLITransport::new("Hello", options, Some("/usr/bin/claude"))
+        .unwrap()
+        .build_command()
+}
+
+fn expected(args: &[&str]) -> Vec<String> {
+    let mut expected = vec!["/usr/bin/claude", "--output-format", "stream-json", "--verbose"];
+    expected.extend(args);
+    expected.extend(["--print", "Hello"]);
+    expected.into_iter().map(String::from).collect()
+}
+
+#[test]
+fn test_each_option_produces_exact_argv() {
+    // Default options add no arguments of their own
+    assert_eq!(argv(ClaudeCodeOptions::default()), expected(&[]));
+
+    for (field, set, args) in CASES {
+        let mut options = ClaudeCodeOptions::default();
+        set(&mut options);
+        assert_eq!(argv(options), expected(args), "arguments for {}", field);
+    }
+}
+
+#[test]
+fn test_every_option_field_has_a_case() {
+    let fields: BTreeSet<String> = match serde_json::to_value(ClaudeCodeOptions::default()).unwrap() {
+        serde_json::Value::Object(fields) => fields.keys().cloned().collect(),
+        other => panic!("Options should serialize to a map, got {}", other),
+    };
+    let covered: BTreeSet<String> = CASES.iter().map(|(field, _, _)| field.to_string()).collect();
+    assert_eq!(fields, covered, "add a row to CASES for every new option");
+}
+
+#[test]
+fn test_combined_options_keep_order() {
+    let options = ClaudeCodeOptions {
+        allowed_tools: vec!["Read".into(), "mcp__github__search".into()],
+        mcp_tools: vec!["mcp__github__search".into(), "mcp__jira__create".into()],
+        model: Some("opus".into()),
+        fallback_models: vec!["opus".into(), "sonnet".into()],
+        max_turns: Some(2),
+        max_thinking_tokens: Some(8000),
+        ..ClaudeCodeOptions::default()
+    };
+    assert_eq!(
+        argv(options),
+        expected(&[
+            "--allowedTools",
+            "Read,mcp__github__search,mcp__jira__create",
+            "--max-turns",
+            "2",
+            "--max-thinking-tokens",
+            "8000",
+            "--model",
+            "opus",
+            "--fallback-model",
+            "sonnet",
+        ])
+    );
+}
+
+#[test]
+fn test_settings_file_and_empty_sources() {
+    let options = ClaudeCodeOptions {
+        settings: Some(Settings::File(PathBuf::from("/etc/claude/settings.json"))),
+        setting_sources: Some(Vec::new()),
+        ..ClaudeCodeOptions::default()
+    };
+    assert_eq!(
+        argv(options),
+        expected(&["--settings", "/etc/claude/settings.json", "--setting-sources", ""])
+    );
+}
+
+#[test]
+fn test_cli_path_from_options() {
+    let options = ClaudeCodeOptions {
+        cli_path: Some(PathBuf::from("/opt/claude/bin/claude")),
+        ..ClaudeCodeOptions::default()
+    };
+    let argv = SubprocessCLITransport::new("Hello", options, None).unwrap().build_command();
+    assert_eq!(argv[0], "/opt/claude/bin/claude");
+}
@@ -0,0 +1,124 @@
+//! Tests for running queries with their own config directory.
+
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+
+use claude_code_sdk::{query, ClaudeCodeOptions, ConfigDir, Message};
+use tokio_stream::StreamExt;
+
+mod common;
+
+/// Run a query and return the config dir the CLI saw and the files in it
+async fn run(options: ClaudeCodeOptions, log: &Path) -> (PathBuf, Vec<String>) {
+    let messages: Vec<Message> = query("Hello", Some(options)).await.unwrap().collect().await;
+    assert!(matches!(messages.last(), Some(Message::Result(_))));
+
+    let contents = std::fs::read_to_string(log).unwrap();
+    let line = contents.lines().last().unwrap();
+    let (dir, files) = line.split_once('|').unwrap();
+    (PathBuf::from(dir), files.split_whitespace().map(String::from).collect())
+}
+
+#[tokio::test]
+async fn test_query_uses_config_dir() {
+    // Records the config dir and its contents, then writes a session into it
+    let (temp_dir, cli) = common::fake_cli(
+        r#"echo "$CLAUDE_CONFIG_DIR|$(ls -A "$CLAUDE_CONFIG_DIR" | tr '\n' ' ')" >> "$dir/log.txt"
+echo '{"theme": "dark"}' > "$CLAUDE_CONFIG_DIR/settings.json"
+mkdir -p "$CLAUDE_CONFIG_DIR/projects"
+echo '{}' > "$CLAUDE_CONFIG_DIR/projects/s1.jsonl"
+echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 20, "duration_api_ms": 10, "num_turns": 1, "session_id": "s1"}'"#,
+    );
+    let dir = temp_dir.path();
+    let log = dir.join("log.txt");
+    let sdk_config_dir = std::env::var_os("CLAUDE_CONFIG_DIR");
+
+    let template = dir.join("template");
+    std::fs::create_dir_all(template.join("agents")).unwrap();
+    std::fs::write(template.join(".credentials.json"), "{}").unwrap();
+    std::fs::write(template.join("settings.json"), r#"{"theme": "light"}"#).unwrap();
+    std::fs::write(template.join("agents/reviewer.md"), "Review code").unwrap();
+
+    // A temporary directory is seeded from the template and removed afterwards
+    let options = ClaudeCodeOptions {
+        config_dir: Some(ConfigDir::temporary().with_template(&template)),
+        cli_path: Some(cli.clone()),
+        ..Default::default()
+    };
+    let (temp, files) = run(options, &log).await;
+    assert!(temp.starts_with(std::env::temp_dir()));
+    assert_eq!(files, vec![".credentials.json", "agents", "settings.json"]);
+    assert!(!temp.exists(), "temporary config dir should be removed");
+
+    // Each query gets its own temporary directory
+    let options = ClaudeCodeOptions {
+        config_dir: Some(ConfigDir::temporary()),
+        cli_path: Some(cli.clone()),
+        ..Default::default()
+    };
+    let (other, files) = run(options, &log).await;
+    assert_ne!(other, temp);
+    assert!(files.is_empty());
+
+    // A fixed directory is kept, and the template does not overwrite what it already has
+    let fixed = dir.join("agent-1");
+    let options = ClaudeCodeOptions {
+        config_dir: Some(ConfigDir::at(&fixed).with_template(&template)),
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let (seen, _) = run(options.clone(), &log).await;
+    assert_eq!(seen, fixed);
+    assert!(fixed.join("projects/s1.jsonl").exists());
+    assert_eq!(std::fs::read_to_string(fixed.join("settings.json")).unwrap().trim(), r#"{"theme": "dark"}"#);
+
+    let (_, files) = run(options, &log).await;
+    assert_eq!(files, vec![".credentials.json", "agents", "projects", "settings.json"]);
+    assert_eq!(std::fs::read_to_string(fixed.join("settings.json")).unwrap().trim(), r#"{"theme": "dark"}"#);
+
+    // The SDK's own environment is untouched
+    assert_eq!(std::env::var_os("CLAUDE_CONFIG_DIR"), sdk_config_dir);
+}
+
+#[tokio::test]
+async fn test_template_symlinks_stay_inside_template() {
+    let (temp_dir, cli) = common::fake_cli(
+        r#"echo "$CLAUDE_CONFIG_DIR|$(ls -A "$CLAUDE_CONFIG_DIR" | tr '\n' ' ')" >> "$dir/log.txt"
+echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 20, "duration_api_ms": 10, "num_turns": 1, "session_id": "s1"}'"#,
+    );
+    let dir = temp_dir.path();
+
+    let outside = dir.join("outside");
+    std::fs::create_dir_all(outside.join("secrets")).unwrap();
+    std::fs::write(outside.join("secrets/key"), "secret").unwrap();
+    std::fs::write(outside.join("settings.json"), "{}").unwrap();
+    let template = dir.join("template");
+    std::fs::create_dir_all(template.join("agents")).unwrap();
+    std::fs::write(template.join("agents/reviewer.md"), "Review code").unwrap();
+    std::fs::write(template.join("base.json"), r#"{"theme": "light"}"#).unwrap();
+
+    // Links inside the template are followed; absolute and `../` links out of it are not
+    std::os::unix::fs::symlink("base.json", template.join("settings.json")).unwrap();
+    std::os::unix::fs::symlink(template.join("agents"), template.join("shared-agents")).unwrap();
+    std::os::unix::fs::symlink(outside.join("secrets"), template.join("secrets")).unwrap();
+    std::os::unix::fs::symlink("../outside/settings.json", template.join("outside.json")).unwrap();
+    std::os::unix::fs::symlink("..", template.join("agents/up")).unwrap();
+    std::os::unix::fs::symlink("missing.json", template.join("dangling.json")).unwrap();
+
+    let fixed = dir.join("agent-1");
+    let options = ClaudeCodeOptions {
+        config_dir: Some(ConfigDir::at(&fixed).with_template(&template)),
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let (_, files) = run(options, &dir.join("log.txt")).await;
+    assert_eq!(files, vec!["agents", "base.json", "settings.json", "shared-agents"]);
+
+    // What was copied are plain files holding the template's contents
+    let settings = fixed.join("settings.json");
+    assert!(!std::fs::symlink_metadata(&settings).unwrap().file_type().is_symlink());
+    assert_eq!(std::fs::read_to_string(settings).unwrap(), r#"{"theme": "light"}"#);
+    assert_eq!(std::fs::read_to_string(fixed.join("shared-agents/reviewer.md")).unwrap(), "Review code");
+    assert!(!fixed.join("agents/up").exists());
+}
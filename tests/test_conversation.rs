@@ -0,0 +1,63 @@
+//! Tests for multi-turn conversations.
+
+#![cfg(unix)]
+
+use claude_code_sdk::{ClaudeCodeOptions, Conversation, Message};
+use tokio_stream::StreamExt;
+
+mod common;
+
+async fn ask(conversation: &Conversation, prompt: &str) -> Vec<Message> {
+    conversation.ask(prompt).await.unwrap().collect().await
+}
+
+#[tokio::test]
+async fn test_conversation_resumes_and_forks() {
+    // A stand-in CLI that reports s1 for new sessions, s2 for forks and
+    // otherwise stays in the resumed session
+    let (dir, cli) = common::fake_cli(
+        r#"session=s1
+prev=""
+fork=""
+for arg in "$@"; do
+  if [ "$prev" = "--resume" ]; then session="$arg"; fi
+  if [ "$arg" = "--fork-session" ]; then fork=1; fi
+  prev="$arg"
+done
+if [ -n "$fork" ]; then session=s2; fi
+echo "{\"type\": \"system\", \"subtype\": \"init\", \"session_id\": \"$session\"}"
+echo "{\"type\": \"result\", \"subtype\": \"success\", \"is_error\": false, \"duration_ms\": 1, \"duration_api_ms\": 1, \"num_turns\": 1, \"session_id\": \"$session\", \"result\": \"ok\"}""#,
+    );
+    let args = |n: u32| std::fs::read_to_string(dir.path().join(format!("args_{}", n))).unwrap();
+
+    let conversation = Conversation::new(ClaudeCodeOptions {
+        model: Some("claude-sonnet-4-5".to_string()),
+        cli_path: Some(cli),
+        ..Default::default()
+    });
+    assert_eq!(conversation.session_id(), None);
+
+    ask(&conversation, "Read the schema").await;
+    assert_eq!(conversation.session_id().as_deref(), Some("s1"));
+    assert!(!args(1).contains("--resume"));
+    assert!(args(1).contains("--model claude-sonnet-4-5"));
+
+    ask(&conversation, "Now add an index").await;
+    assert!(args(2).contains("--resume s1"));
+    assert!(args(2).contains("--model claude-sonnet-4-5"));
+
+    let branch = conversation.fork();
+    assert_eq!(branch.session_id().as_deref(), Some("s1"));
+    ask(&branch, "Try a partial index instead").await;
+    assert!(args(3).contains("--resume s1 --fork-session"));
+    assert_eq!(branch.session_id().as_deref(), Some("s2"));
+    assert_eq!(conversation.session_id().as_deref(), Some("s1"), "The original is untouched");
+
+    ask(&branch, "And benchmark it").await;
+    assert!(args(4).contains("--resume s2"));
+    assert!(!args(4).contains("--fork-session"));
+
+    let resumed = Conversation::resume("s1", ClaudeCodeOptions::default());
+    assert_eq!(resumed.session_id().as_deref(), Some("s1"));
+    assert!(resumed.options().resume.is_none(), "The session lives in the conversation");
+}
@@ -1,6 +1,11 @@
 //! Tests for Claude SDK error types.
 
-use claude_code_sdk::{CLIConnectionError, CLIJSONDecodeError, CLINotFoundError, ProcessError};
+use claude_code_sdk::{
+    classify_api_error, CLIConnectionError, CLIJSONDecodeError, CLINotFoundError, ClaudeSDKError,
+    ProcessError, ResultMessage,
+};
+use std::collections::HashMap;
+use std::time::Duration;
 
 #[test]
 fn test_cli_not_found_error() {
@@ -74,4 +79,150 @@ fn test_cli_json_decode_error_truncation() {
     
     assert!(display_string.contains("..."));
     assert!(display_string.len() < long_line.len() + 50); // Should be truncated
-} 
\ No newline at end of file
+} 
+
+fn result_message(subtype: &str, is_error: bool) -> ResultMessage {
+    let mut usage = HashMap::new();
+    usage.insert("output_tokens".to_string(), serde_json::json!(42));
+    ResultMessage {
+        subtype: subtype.to_string(),
+        duration_ms: 1000,
+        duration_api_ms: 800,
+        is_error,
+        num_turns: 5,
+        session_id: "session-123".to_string(),
+        total_cost_usd: Some(1.5),
+        usage: Some(usage),
+        result: None,
+        model: None,
+        estimated_cost_usd: None,
+    }
+}
+
+#[test]
+fn test_into_result_success() {
+    let result = result_message("success", false).into_result();
+    assert_eq!(result.unwrap().session_id, "session-123");
+}
+
+#[test]
+fn test_into_result_max_turns() {
+    match result_message("error_max_turns", true).into_result() {
+        Err(ClaudeSDKError::MaxTurnsReached(e)) => {
+            assert_eq!(e.session_id(), "session-123");
+            assert_eq!(e.usage().unwrap()["output_tokens"], 42);
+            assert!(e.to_string().contains("5 turns"));
+        }
+        other => panic!("Expected max turns error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_into_result_budget_and_execution_errors() {
+    assert!(matches!(
+        result_message("error_max_budget_usd", true).into_result(),
+        Err(ClaudeSDKError::BudgetExceeded(_))
+    ));
+    assert!(matches!(
+        result_message("error_during_execution", true).into_result(),
+        Err(ClaudeSDKError::Execution(_))
+    ));
+
+    // Failed unknown subtypes and failed "success" results are execution errors
+    assert!(matches!(
+        result_message("error_new_kind", true).into_result(),
+        Err(ClaudeSDKError::Execution(_))
+    ));
+    // An unknown subtype alone is not a failure
+    assert!(result_message("partial_success", false).into_result().is_ok());
+    let mut failed = result_message("success", true);
+    failed.result = Some("API Error: boom".to_string());
+    match failed.into_result() {
+        Err(ClaudeSDKError::Execution(e)) => assert_eq!(e.message, "API Error: boom"),
+        other => panic!("Expected execution error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_classify_api_error_from_cli_json() {
+    let text = r#"API Error: 429 {"type":"error","error":{"type":"rate_limit_error","message":"Number of requests has exceeded your rate limit"}} retry-after: 30"#;
+    match classify_api_error(text) {
+        Some(ClaudeSDKError::RateLimited(e)) => {
+            assert_eq!(e.status, Some(429));
+            assert_eq!(e.retry_after, Some(Duration::from_secs(30)));
+            assert_eq!(e.message, "Number of requests has exceeded your rate limit");
+        }
+        other => panic!("Expected rate limit error, got {:?}", other),
+    }
+
+    let text = r#"API Error: 529 {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+    assert!(matches!(classify_api_error(text), Some(ClaudeSDKError::Overloaded(_))));
+
+    let text = r#"API Error: 400 {"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 215000 tokens > 200000 maximum"}}"#;
+    assert!(matches!(classify_api_error(text), Some(ClaudeSDKError::PromptTooLong(_))));
+
+    let text = r#"API Error: 404 {"type":"error","error":{"type":"not_found_error","message":"model: claude-nonexistent"}}"#;
+    assert!(matches!(classify_api_error(text), Some(ClaudeSDKError::InvalidModel(_))));
+
+    let text = r#"API Error: 401 {"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#;
+    assert!(matches!(classify_api_error(text), Some(ClaudeSDKError::Authentication(_))));
+}
+
+#[test]
+fn test_classify_api_error_from_plain_text() {
+    assert!(matches!(
+        classify_api_error("Invalid API key · Please run /login"),
+        Some(ClaudeSDKError::Authentication(_))
+    ));
+    assert!(matches!(
+        classify_api_error("API Error: 529 Overloaded"),
+        Some(ClaudeSDKError::Overloaded(e)) if e.status == Some(529)
+    ));
+    assert!(classify_api_error("Tool execution failed: file not found").is_none());
+}
+
+#[test]
+fn test_into_result_classifies_api_errors() {
+    let mut failed = result_message("success", true);
+    failed.result = Some(r#"API Error: 529 {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#.to_string());
+
+    match failed.into_result() {
+        Err(error @ ClaudeSDKError::Overloaded(_)) => {
+            assert_eq!(error.api_error().unwrap().session_id.as_deref(), Some("session-123"));
+        }
+        other => panic!("Expected overloaded error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_into_result_checks_subtype_before_text() {
+    // Hitting max_turns stays a max turns error whatever the last answer said
+    let mut max_turns = result_message("error_max_turns", true);
+    max_turns.result = Some("The API is overloaded, retry after a rate limit".to_string());
+    assert!(matches!(max_turns.into_result(), Err(ClaudeSDKError::MaxTurnsReached(_))));
+
+    // A successful answer that talks about rate limits is still a success
+    let mut answer = result_message("success", false);
+    answer.result = Some("Added a rate limit check and a context window guard".to_string());
+    assert!(answer.into_result().is_ok());
+
+    // Failed runs are only classified from the CLI's API Error prefix
+    let mut failed = result_message("success", true);
+    failed.result = Some("Tool crashed: server overloaded".to_string());
+    assert!(matches!(failed.into_result(), Err(ClaudeSDKError::Execution(_))));
+    let mut failed = result_message("error_during_execution", true);
+    failed.result = Some("API Error: 529 Overloaded".to_string());
+    assert!(matches!(failed.into_result(), Err(ClaudeSDKError::Execution(_))));
+}
+
+#[test]
+fn test_into_result_classifies_stderr_results() {
+    // A run that ended without a result, reported from its stderr
+    let mut failed = result_message("error", true);
+    failed.result = Some("Starting session\nAPI Error: 429 rate limited, retry-after: 5".to_string());
+    assert!(matches!(failed.into_result(), Err(ClaudeSDKError::RateLimited(_))));
+
+    let mut failed = result_message("error", true);
+    failed.result = Some("Segmentation fault".to_string());
+    assert!(matches!(failed.into_result(), Err(ClaudeSDKError::Execution(_))));
+}
@@ -0,0 +1,70 @@
+//! Tests for the fallback model chain.
+
+#![cfg(unix)]
+
+use claude_code_sdk::{query, ClaudeCodeOptions, Message};
+use tokio_stream::StreamExt;
+
+mod common;
+
+#[tokio::test]
+async fn test_query_falls_back_to_next_model() {
+    // A stand-in CLI that is overloaded on its first run and succeeds afterwards
+    let (dir, cli) = common::fake_cli(
+        r#"if [ $n -eq 1 ]; then
+  echo '{"type": "system", "subtype": "init", "session_id": "s1", "model": "claude-opus-4-1"}'
+  echo '{"type": "result", "subtype": "success", "is_error": true, "duration_ms": 1, "duration_api_ms": 1, "num_turns": 1, "session_id": "s1", "result": "API Error: 529 Overloaded"}'
+else
+  echo '{"type": "assistant", "message": {"id": "msg_2", "model": "claude-sonnet-4-5", "content": [{"type": "text", "text": "done"}]}}'
+  echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 1, "duration_api_ms": 1, "num_turns": 2, "session_id": "s1", "result": "done"}'
+fi"#,
+    );
+
+    let options = ClaudeCodeOptions {
+        model: Some("claude-opus-4-1".to_string()),
+        fallback_models: vec!["claude-sonnet-4-5".to_string(), "claude-haiku-4-5".to_string()],
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let messages: Vec<Message> = query("Fix the migration", Some(options)).await.unwrap().collect().await;
+
+    let results: Vec<_> = messages
+        .iter()
+        .filter_map(|m| match m {
+            Message::Result(result) => Some(result),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(results.len(), 1, "The overloaded run's result should be hidden");
+    assert!(!results[0].is_error);
+    assert_eq!(results[0].model.as_deref(), Some("claude-sonnet-4-5"));
+
+    let first_args = std::fs::read_to_string(dir.path().join("args_1")).unwrap();
+    assert!(first_args.contains("--model claude-opus-4-1"));
+    assert!(first_args.contains("--fallback-model claude-sonnet-4-5"));
+
+    let fallback_args = std::fs::read_to_string(dir.path().join("args_2")).unwrap();
+    assert!(fallback_args.contains("--model claude-sonnet-4-5"));
+    assert!(fallback_args.contains("--fallback-model claude-haiku-4-5"));
+    assert!(fallback_args.contains("--resume s1"));
+}
+
+#[tokio::test]
+async fn test_result_model_comes_from_cli_output() {
+    // Neither the init message nor the requested model says who answered
+    let (_dir, cli) = common::fake_cli(
+        r#"echo '{"type": "system", "subtype": "init", "session_id": "s1", "model": "claude-opus-4-1"}'
+echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 1, "duration_api_ms": 1, "num_turns": 1, "session_id": "s1", "result": "done"}'"#,
+    );
+
+    let options = ClaudeCodeOptions {
+        model: Some("claude-opus-4-1".to_string()),
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let messages: Vec<Message> = query("Fix the migration", Some(options)).await.unwrap().collect().await;
+    match messages.last() {
+        Some(Message::Result(result)) => assert_eq!(result.model, None),
+        other => panic!("Expected a result, got {:?}", other),
+    }
+}
@@ -0,0 +1,215 @@
+//! Tests for the cost and usage ledger.
+
+use std::path::PathBuf;
+
+use chrono::{TimeZone, Utc};
+use claude_code_sdk::{Ledger, LedgerRecord, ResultMessage, Usage};
+
+mod common;
+
+fn record(day: u32, model: &str, project: &str, cost: f64) -> LedgerRecord {
+    LedgerRecord {
+        timestamp: Utc.with_ymd_and_hms(2025, 3, day, 12, 0, 0).unwrap(),
+        session_id: format!("session-{}", day),
+        model: Some(model.to_string()),
+        cwd: Some(PathBuf::from(project)),
+        usage: Usage {
+            input_tokens: 100,
+            output_tokens: 10,
+            ..Usage::default()
+        },
+        cost_usd: Some(cost),
+        cost_is_estimate: false,
+        duration_ms: 1000,
+        num_turns: 2,
+        subtype: "success".to_string(),
+        is_error: false,
+    }
+}
+
+#[test]
+fn test_record_from_result() {
+    let result = ResultMessage {
+        subtype: "error_max_turns".to_string(),
+        duration_ms: 1500,
+        duration_api_ms: 1200,
+        is_error: true,
+        num_turns: 5,
+        session_id: "s1".to_string(),
+        total_cost_usd: Some(0.25),
+        usage: Some(Usage { input_tokens: 7, ..Usage::default() }.to_map()),
+        result: None,
+        model: Some("claude-sonnet-4-5".to_string()),
+        estimated_cost_usd: None,
+    };
+    let record = LedgerRecord::from_result(&result, Some(std::path::Path::new("/work/api")));
+    assert_eq!(record.session_id, "s1");
+    assert_eq!(record.usage.input_tokens, 7);
+    assert_eq!(record.cost_usd, Some(0.25));
+    assert_eq!(record.num_turns, 5);
+    assert!(record.is_error);
+    assert_eq!(record.cwd, Some(PathBuf::from("/work/api")));
+}
+
+#[test]
+fn test_ledger_append_and_totals() {
+    let dir = common::temp_dir("ledger-totals");
+    let ledger = Ledger::new(dir.join("nested").join("ledger.jsonl"));
+    assert!(ledger.records().unwrap().is_empty(), "A missing ledger reads as empty");
+
+    ledger.append(&record(1, "claude-sonnet-4-5", "/work/api", 0.5)).unwrap();
+    ledger.append(&record(1, "claude-opus-4-1", "/work/web", 2.0)).unwrap();
+    ledger.append(&record(2, "claude-sonnet-4-5", "/work/api", 0.25)).unwrap();
+
+    let records = ledger.records().unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0], record(1, "claude-sonnet-4-5", "/work/api", 0.5));
+
+    let total = ledger.total().unwrap();
+    assert_eq!(total.queries, 3);
+    assert_eq!(total.usage.input_tokens, 300);
+    assert!((total.cost_usd - 2.75).abs() < 1e-9);
+
+    let by_day = ledger.totals_by_day().unwrap();
+    assert_eq!(by_day.keys().collect::<Vec<_>>(), vec!["2025-03-01", "2025-03-02"]);
+    assert_eq!(by_day["2025-03-01"].queries, 2);
+
+    let by_model = ledger.totals_by_model().unwrap();
+    assert!((by_model["claude-sonnet-4-5"].cost_usd - 0.75).abs() < 1e-9);
+
+    let by_project = ledger.totals_by_project().unwrap();
+    assert_eq!(by_project[&Some(PathBuf::from("/work/web"))].queries, 1);
+
+    let march_first = ledger
+        .records_between(
+            Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2025, 3, 2, 0, 0, 0).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(march_first.len(), 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_ledger_skips_malformed_lines() {
+    let dir = common::temp_dir("ledger-malformed");
+    let ledger = Ledger::new(dir.join("ledger.jsonl"));
+    ledger.append(&record(1, "claude-sonnet-4-5", "/work/api", 0.5)).unwrap();
+    let mut contents = std::fs::read_to_string(ledger.path()).unwrap();
+    contents.push_str("{\"truncated\n\n");
+    std::fs::write(ledger.path(), contents).unwrap();
+    ledger.append(&record(2, "claude-sonnet-4-5", "/work/api", 0.5)).unwrap();
+
+    assert_eq!(ledger.records().unwrap().len(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_query_appends_to_ledger() {
+    use claude_code_sdk::{query, ClaudeCodeOptions, Message};
+    use tokio_stream::StreamExt;
+
+    let (dir, cli) = common::fake_cli(
+        r#"echo '{"type": "system", "subtype": "init", "session_id": "s1", "model": "claude-haiku-4-5"}'
+echo '{"type": "assistant", "message": {"id": "msg_1", "model": "claude-haiku-4-5", "content": [{"type": "text", "text": "done"}]}}'
+echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 20, "duration_api_ms": 10, "num_turns": 1, "session_id": "s1", "usage": {"input_tokens": 1000000, "output_tokens": 0}, "result": "done"}'"#,
+    );
+    let dir = dir.path();
+
+    let ledger = Ledger::new(dir.join("ledger.jsonl"));
+    let options = ClaudeCodeOptions {
+        cwd: Some(dir.to_path_buf()),
+        ledger: Some(ledger.clone()),
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let messages: Vec<Message> = query("Summarize", Some(options)).await.unwrap().collect().await;
+    assert!(matches!(messages.last(), Some(Message::Result(_))));
+
+    let records = ledger.records().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].session_id, "s1");
+    assert_eq!(records[0].model.as_deref(), Some("claude-haiku-4-5"));
+    assert_eq!(records[0].cwd.as_deref(), Some(dir));
+    assert_eq!(records[0].usage.input_tokens, 1_000_000);
+    // No reported cost, so the usage is priced and marked as an estimate
+    assert_eq!(records[0].cost_usd, Some(1.0));
+    assert!(records[0].cost_is_estimate);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_ledger_sums_retried_attempts() {
+    use claude_code_sdk::{query, ClaudeCodeOptions, Message, RetryPolicy};
+    use tokio_stream::StreamExt;
+
+    // Overloaded on the first run, which still used and cost something
+    let (dir, cli) = common::fake_cli(
+        r#"if [ $n -eq 1 ]; then
+  echo '{"type": "result", "subtype": "success", "is_error": true, "duration_ms": 5, "duration_api_ms": 1, "num_turns": 1, "session_id": "s1", "total_cost_usd": 0.1, "usage": {"input_tokens": 100, "output_tokens": 10}, "result": "API Error: 529 Overloaded"}'
+else
+  echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 7, "duration_api_ms": 1, "num_turns": 2, "session_id": "s1", "total_cost_usd": 0.2, "usage": {"input_tokens": 200, "output_tokens": 20}, "result": "done"}'
+fi"#,
+    );
+    let dir = dir.path();
+
+    let ledger = Ledger::new(dir.join("ledger.jsonl"));
+    let options = ClaudeCodeOptions {
+        ledger: Some(ledger.clone()),
+        retry_policy: Some(RetryPolicy {
+            initial_backoff: std::time::Duration::from_millis(10),
+            ..RetryPolicy::default()
+        }),
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let _: Vec<Message> = query("Summarize", Some(options)).await.unwrap().collect().await;
+
+    // Usage, cost, time and turns all cover the two attempts
+    let records = ledger.records().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].usage.input_tokens, 300);
+    assert_eq!(records[0].usage.output_tokens, 30);
+    assert!((records[0].cost_usd.unwrap() - 0.3).abs() < 1e-9);
+    assert!(!records[0].cost_is_estimate);
+    assert_eq!(records[0].duration_ms, 12);
+    assert_eq!(records[0].num_turns, 3);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_ledger_marks_estimate_from_retried_attempt() {
+    use claude_code_sdk::{query, ClaudeCodeOptions, Message, RetryPolicy};
+    use tokio_stream::StreamExt;
+
+    // The first run reports no cost, so its share is priced from usage
+    let (dir, cli) = common::fake_cli(
+        r#"if [ $n -eq 1 ]; then
+  echo '{"type": "result", "subtype": "success", "is_error": true, "duration_ms": 5, "duration_api_ms": 1, "num_turns": 1, "session_id": "s1", "model": "claude-sonnet-4-5", "usage": {"input_tokens": 1000000, "output_tokens": 0}, "result": "API Error: 529 Overloaded"}'
+else
+  echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 7, "duration_api_ms": 1, "num_turns": 1, "session_id": "s1", "total_cost_usd": 0.2, "usage": {"input_tokens": 200, "output_tokens": 20}, "result": "done"}'
+fi"#,
+    );
+    let dir = dir.path();
+
+    let ledger = Ledger::new(dir.join("ledger.jsonl"));
+    let options = ClaudeCodeOptions {
+        ledger: Some(ledger.clone()),
+        retry_policy: Some(RetryPolicy {
+            initial_backoff: std::time::Duration::from_millis(10),
+            ..RetryPolicy::default()
+        }),
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let _: Vec<Message> = query("Summarize", Some(options)).await.unwrap().collect().await;
+
+    let records = ledger.records().unwrap();
+    assert_eq!(records.len(), 1);
+    assert!((records[0].cost_usd.unwrap() - 3.2).abs() < 1e-9);
+    assert!(records[0].cost_is_estimate);
+    assert_eq!(records[0].duration_ms, 12);
+    assert_eq!(records[0].num_turns, 2);
+}
@@ -0,0 +1,142 @@
+//! Tests for MCP server configuration.
+
+use claude_code_sdk::{ClaudeCodeOptions, McpServerConfig, McpSseServerConfig};
+use serde_json::json;
+
+#[test]
+fn test_servers_serialize_to_cli_schema() {
+    let stdio = McpServerConfig::stdio("npx", ["-y", "@modelcontextprotocol/server-github"])
+        .with_env("GITHUB_TOKEN", "ghp_example");
+    assert_eq!(
+        serde_json::to_value(&stdio).unwrap(),
+        json!({
+            "type": "stdio",
+            "command": "npx",
+            "args": ["-y", "@modelcontextprotocol/server-github"],
+            "env": {"GITHUB_TOKEN": "ghp_example"}
+        })
+    );
+
+    // Empty args, env and headers are left out
+    assert_eq!(
+        serde_json::to_value(McpServerConfig::stdio("gh-mcp", Vec::<String>::new())).unwrap(),
+        json!({"type": "stdio", "command": "gh-mcp"})
+    );
+    assert_eq!(
+        serde_json::to_value(McpServerConfig::sse("https://mcp.example.com/sse")).unwrap(),
+        json!({"type": "sse", "url": "https://mcp.example.com/sse"})
+    );
+    assert_eq!(
+        serde_json::to_value(McpServerConfig::http("https://mcp.example.com/mcp").with_header("Authorization", "Bearer t"))
+            .unwrap(),
+        json!({"type": "http", "url": "https://mcp.example.com/mcp", "headers": {"Authorization": "Bearer t"}})
+    );
+    assert_eq!(
+        serde_json::to_value(McpServerConfig::sdk("calculator")).unwrap(),
+        json!({"type": "sdk", "name": "calculator"})
+    );
+
+    // Settings that do not apply to a kind of server are ignored
+    assert_eq!(
+        McpServerConfig::sse("https://mcp.example.com/sse").with_env("A", "1"),
+        McpServerConfig::sse("https://mcp.example.com/sse")
+    );
+    assert_eq!(McpServerConfig::sdk("calculator").kind(), "sdk");
+}
+
+#[test]
+fn test_servers_deserialize() {
+    // Servers without a type are stdio servers
+    let server: McpServerConfig = serde_json::from_value(json!({"command": "gh-mcp", "args": ["--stdio"]})).unwrap();
+    assert_eq!(server, McpServerConfig::stdio("gh-mcp", ["--stdio"]));
+
+    let server: McpServerConfig =
+        serde_json::from_value(json!({"type": "sse", "url": "https://x.test/sse", "headers": {"X-Key": "k"}})).unwrap();
+    assert_eq!(server, McpServerConfig::sse("https://x.test/sse").with_header("X-Key", "k"));
+    assert!(matches!(server, McpServerConfig::Sse(McpSseServerConfig { ref url, .. }) if url == "https://x.test/sse"));
+
+    for invalid in [
+        json!({"type": "websocket", "url": "wss://x.test"}),
+        json!({"type": "http"}),
+        json!(["gh-mcp"]),
+    ] {
+        assert!(serde_json::from_value::<McpServerConfig>(invalid.clone()).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_unknown_server_keys_are_kept() {
+    // Keys from newer CLI versions or other tools must not break loading
+    let entry = json!({"type": "http", "url": "https://x.test/mcp", "timeout": 30000, "oauth": {"clientId": "abc"}});
+    let server: McpServerConfig = serde_json::from_value(entry.clone()).unwrap();
+    match &server {
+        McpServerConfig::Http(config) => {
+            assert_eq!(config.url, "https://x.test/mcp");
+            assert_eq!(config.extra["timeout"], 30000);
+        }
+        other => panic!("Expected an HTTP server, got {:?}", other),
+    }
+    assert_eq!(serde_json::to_value(&server).unwrap(), entry);
+
+    let server: McpServerConfig = serde_json::from_value(json!({"command": "gh-mcp", "cwd": "/srv"})).unwrap();
+    assert_eq!(serde_json::to_value(&server).unwrap(), json!({"type": "stdio", "command": "gh-mcp", "cwd": "/srv"}));
+}
+
+#[test]
+fn test_load_mcp_json() {
+    let dir = std::env::temp_dir().join(format!("claude-sdk-test-mcp-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(".mcp.json");
+    std::fs::write(
+        &path,
+        r#"{
+  "mcpServers": {
+    "github": {"command": "npx", "args": ["-y", "@modelcontextprotocol/server-github"], "env": {"GITHUB_TOKEN": "${GITHUB_TOKEN}"}},
+    "linear": {"type": "sse", "url": "https://mcp.linear.app/sse", "description": "Issue tracker"},
+    "docs": {"type": "http", "url": "https://docs.example.com/mcp", "headers": {"Authorization": "Bearer ${DOCS_TOKEN}"}}
+  }
+}"#,
+    )
+    .unwrap();
+
+    let servers = McpServerConfig::load(&path).unwrap();
+    assert_eq!(servers.len(), 3);
+    assert_eq!(
+        servers["github"],
+        McpServerConfig::stdio("npx", ["-y", "@modelcontextprotocol/server-github"]).with_env("GITHUB_TOKEN", "${GITHUB_TOKEN}")
+    );
+    assert_eq!(servers["linear"].kind(), "sse");
+    assert_eq!(
+        servers["docs"],
+        McpServerConfig::http("https://docs.example.com/mcp").with_header("Authorization", "Bearer ${DOCS_TOKEN}")
+    );
+
+    let options = ClaudeCodeOptions::builder().mcp_servers(servers).build().unwrap();
+    assert_eq!(options.mcp_servers.len(), 3);
+
+    std::fs::write(&path, r#"{"mcpServers": {"bad": {"type": "ftp"}}}"#).unwrap();
+    let error = McpServerConfig::load(&path).unwrap_err();
+    assert!(error.message.contains("Invalid MCP config"), "{}", error.message);
+    assert!(McpServerConfig::load(dir.join("missing.json")).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_builder_checks_mcp_servers() {
+    let error = ClaudeCodeOptions::builder()
+        .mcp_server("local", McpServerConfig::stdio(" ", Vec::<String>::new()))
+        .mcp_server("remote", McpServerConfig::http("mcp.example.com"))
+        .mcp_server("sdk", McpServerConfig::sdk("calculator"))
+        .mcp_server("ok", McpServerConfig::sse("https://mcp.example.com/sse"))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error.problems,
+        vec![
+            "MCP server local has no command",
+            "MCP server remote needs an http or https URL, got \"mcp.example.com\"",
+            "MCP server sdk is an SDK server, which this SDK cannot serve yet",
+        ]
+    );
+}
@@ -1,6 +1,8 @@
-use claude_code_sdk::{ClaudeCodeOptions, SafetyLimits};
+use claude_code_sdk::{ClaudeCodeOptions, ClaudeSDKError, SafetyLimits};
 use claude_code_sdk::transport::subprocess_cli::SubprocessCLITransport;
 
+mod common;
+
 #[tokio::test]
 async fn test_single_line_json_parsing() {
     // Test that single-line JSON continues to work as expected
@@ -215,4 +217,165 @@ async fn test_mixed_single_and_multiline_json() {
     // Another single-line JSON
     let result6 = transport.process_line(r#"{"type": "another", "single": true}"#.to_string());
     assert!(result6.is_some(), "Another single-line JSON should parse");
-} 
\ No newline at end of file
+} 
+
+#[tokio::test]
+async fn test_braces_inside_strings_do_not_affect_framing() {
+    let options = ClaudeCodeOptions::default();
+    let mut transport = SubprocessCLITransport::new("test", options, Some("nonexistent"))
+        .expect("Should create transport");
+
+    let result1 = transport.process_line(r#"{"type": "text","#.to_string());
+    assert!(result1.is_none(), "Should wait for more JSON data");
+
+    // Closing braces inside a string must not end the frame early
+    let result2 = transport.process_line(r#"  "text": "}} fn main() { \"}\" }","#.to_string());
+    assert!(result2.is_none(), "Braces in strings should not complete the frame");
+
+    let result3 = transport.process_line(r#"  "done": true}"#.to_string());
+    let data = result3.expect("Frame should complete").expect("Should parse");
+    assert_eq!(data.get("text").unwrap().as_str().unwrap(), r#"}} fn main() { "}" }"#);
+    assert!(data.get("done").unwrap().as_bool().unwrap());
+}
+
+#[tokio::test]
+async fn test_bytes_split_across_reads() {
+    let options = ClaudeCodeOptions::default();
+    let mut transport = SubprocessCLITransport::new("test", options, Some("nonexistent"))
+        .expect("Should create transport");
+
+    let output = b"{\"type\": \"first\", \"text\": \"a\\\"b\"}\nlog line\n{\"type\": \"second\"}\n";
+    let mut messages = Vec::new();
+
+    // Feed one byte at a time to exercise every split point
+    for byte in output.iter() {
+        transport.process_bytes(std::slice::from_ref(byte));
+        while let Some(result) = transport.next_message() {
+            messages.push(result.expect("Should parse"));
+        }
+    }
+
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].get("type").unwrap().as_str().unwrap(), "first");
+    assert_eq!(messages[0].get("text").unwrap().as_str().unwrap(), "a\"b");
+    assert_eq!(messages[1].get("type").unwrap().as_str().unwrap(), "second");
+}
+
+#[tokio::test]
+async fn test_multiple_objects_on_one_line() {
+    let options = ClaudeCodeOptions::default();
+    let mut transport = SubprocessCLITransport::new("test", options, Some("nonexistent"))
+        .expect("Should create transport");
+
+    let first = transport.process_line(r#"{"id": 1} {"id": 2}"#.to_string());
+    assert_eq!(first.unwrap().unwrap().get("id").unwrap().as_u64().unwrap(), 1);
+
+    let second = transport.next_message();
+    assert_eq!(second.unwrap().unwrap().get("id").unwrap().as_u64().unwrap(), 2);
+    assert!(transport.next_message().is_none());
+}
+
+#[tokio::test]
+async fn test_large_multiline_message() {
+    let options = ClaudeCodeOptions::default();
+    let mut transport = SubprocessCLITransport::new("test", options, Some("nonexistent"))
+        .expect("Should create transport");
+
+    // ~8MB spread over many lines stays under the default 10MB limit
+    assert!(transport.process_line(r#"{"type": "big", "items": ["#.to_string()).is_none());
+    let item = format!(r#""{}","#, "x".repeat(1024));
+    for _ in 0..8 * 1024 {
+        assert!(transport.process_line(item.clone()).is_none());
+    }
+    let result = transport.process_line(r#""end"]}"#.to_string());
+
+    let data = result.expect("Frame should complete").expect("Should parse");
+    assert_eq!(data.get("items").unwrap().as_array().unwrap().len(), 8 * 1024 + 1);
+}
+
+#[tokio::test]
+async fn test_resync_when_new_object_interrupts_fragment() {
+    let options = ClaudeCodeOptions::default();
+    let mut transport = SubprocessCLITransport::new("test", options, Some("nonexistent"))
+        .expect("Should create transport");
+
+    // A truncated message that never closes
+    let result1 = transport.process_line(r#"{"type": "assistant", "message": {"id": 1}"#.to_string());
+    assert!(result1.is_none(), "Should wait for more JSON data");
+
+    // The next message must not be swallowed by the broken fragment
+    let result2 = transport.process_line(r#"{"type": "result", "id": 2}"#.to_string());
+    let error = result2.expect("Should report the broken fragment");
+    assert!(matches!(error, Err(ClaudeSDKError::CLIJSONDecode(_))), "Should be a decode error");
+
+    let data = transport.next_message().expect("Should resume parsing").expect("Should parse");
+    assert_eq!(data.get("type").unwrap().as_str().unwrap(), "result");
+    assert!(transport.next_message().is_none());
+}
+
+#[tokio::test]
+async fn test_resync_after_unterminated_string() {
+    let options = ClaudeCodeOptions::default();
+    let mut transport = SubprocessCLITransport::new("test", options, Some("nonexistent"))
+        .expect("Should create transport");
+
+    let result1 = transport.process_line(r#"{"type": "assistant", "text": "cut off"#.to_string());
+    match result1 {
+        Some(Err(ClaudeSDKError::CLIJSONDecode(e))) => assert!(e.line.contains("cut off")),
+        other => panic!("Expected decode error, got {:?}", other),
+    }
+
+    let result2 = transport.process_line(r#"{"type": "system", "subtype": "init"}"#.to_string());
+    let data = result2.expect("Should parse the next message").expect("Should parse");
+    assert_eq!(data.get("type").unwrap().as_str().unwrap(), "system");
+}
+
+#[tokio::test]
+async fn test_resync_after_line_budget_exceeded() {
+    let options = ClaudeCodeOptions::default();
+    let limits = SafetyLimits::default().with_max_json_lines(3);
+    let mut transport = SubprocessCLITransport::new("test", options, Some("nonexistent"))
+        .expect("Should create transport")
+        .with_safety_limits(limits);
+
+    assert!(transport.process_line(r#"{"items": ["#.to_string()).is_none());
+    assert!(transport.process_line(r#"  1,"#.to_string()).is_none());
+    assert!(transport.process_line(r#"  2,"#.to_string()).is_none());
+
+    // The fourth newline exceeds the three-line budget
+    let result = transport.process_line(r#"  3,"#.to_string());
+    assert!(matches!(result, Some(Err(ClaudeSDKError::CLIJSONDecode(_)))), "Should give up on the fragment");
+
+    // Leftover lines of the abandoned fragment are skipped as non-JSON
+    assert!(transport.process_line(r#"  4]}"#.to_string()).is_none());
+
+    let result = transport.process_line(r#"{"type": "result"}"#.to_string());
+    assert!(result.expect("Should resume parsing").is_ok());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_query_reports_malformed_output() {
+    use claude_code_sdk::{query, Message, JSON_DECODE_ERROR_SUBTYPE};
+    use tokio_stream::StreamExt;
+
+    let (_dir, cli) = common::fake_cli(
+        r#"echo '{"type": "assistant", "message": {"id": "msg_1"}'
+echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 20, "duration_api_ms": 10, "num_turns": 1, "session_id": "s1", "total_cost_usd": 0.01, "result": "done"}'"#,
+    );
+    let options = ClaudeCodeOptions {
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let messages: Vec<Message> = query("Hi", Some(options)).await.unwrap().collect().await;
+
+    match &messages[0] {
+        Message::System(system) => {
+            assert_eq!(system.subtype, JSON_DECODE_ERROR_SUBTYPE);
+            assert!(system.data["line"].as_str().unwrap().starts_with(r#"{"type": "assistant""#));
+            assert!(!system.data["error"].as_str().unwrap().is_empty());
+        }
+        other => panic!("Expected a decode error report, got {:?}", other),
+    }
+    assert!(matches!(messages.last(), Some(Message::Result(result)) if !result.is_error));
+}
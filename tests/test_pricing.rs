@@ -0,0 +1,185 @@
+//! Tests for pricing and the cost budget guard.
+
+use claude_code_sdk::{ClaudeSDKError, ModelPricing, PricingTable, ResultMessage, Usage};
+
+mod common;
+
+#[test]
+fn test_model_pricing_cost() {
+    let pricing = ModelPricing::new(3.0, 15.0, 3.75, 0.30);
+    let usage = Usage {
+        input_tokens: 1_000_000,
+        output_tokens: 100_000,
+        cache_creation_input_tokens: 200_000,
+        cache_read_input_tokens: 1_000_000,
+    };
+    let cost = pricing.cost(&usage);
+    assert!((cost - (3.0 + 1.5 + 0.75 + 0.30)).abs() < 1e-9);
+}
+
+#[test]
+fn test_pricing_table_prefix_lookup() {
+    let table = PricingTable::default();
+    let opus_4_5 = table.lookup("claude-opus-4-5-20251101").unwrap();
+    assert_eq!(opus_4_5.input_per_mtok, 5.0);
+    let opus_4 = table.lookup("claude-opus-4-20250514").unwrap();
+    assert_eq!(opus_4.input_per_mtok, 15.0);
+    assert_eq!(table.lookup("sonnet").unwrap().output_per_mtok, 15.0);
+    assert!(table.lookup("gpt-4").is_none());
+
+    let table = PricingTable::empty().with_model("my-model", ModelPricing::new(1.0, 2.0, 0.0, 0.0));
+    let usage = Usage {
+        output_tokens: 500_000,
+        ..Usage::default()
+    };
+    assert_eq!(table.cost("my-model-v2", &usage), Some(1.0));
+    assert_eq!(table.cost("claude-sonnet-4-5", &usage), None);
+}
+
+#[test]
+fn test_usage_from_cli_json() {
+    let value = serde_json::json!({
+        "input_tokens": 12,
+        "cache_creation_input_tokens": 100,
+        "cache_read_input_tokens": 2000,
+        "output_tokens": 34,
+        "service_tier": "standard"
+    });
+    let mut usage = Usage::from_value(&value).unwrap();
+    assert_eq!(usage.input_tokens, 12);
+    assert_eq!(usage.cache_read_input_tokens, 2000);
+    assert_eq!(Usage::from_map(&usage.to_map()), usage);
+
+    usage += usage;
+    assert_eq!(usage.output_tokens, 68);
+}
+
+#[test]
+fn test_pricing_overrides_from_file() {
+    let dir = std::env::temp_dir().join(format!("claude-sdk-test-pricing-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("pricing.json");
+    std::fs::write(
+        &path,
+        r#"{
+            "models": {
+                "claude-sonnet-4-5": {"input_per_mtok": 2.0, "output_per_mtok": 10.0, "cache_write_per_mtok": 2.5, "cache_read_per_mtok": 0.2},
+                "bedrock-claude": {"input_per_mtok": 4.0, "output_per_mtok": 20.0, "cache_write_per_mtok": 5.0, "cache_read_per_mtok": 0.4}
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let table = PricingTable::with_overrides_from_file(&path).unwrap();
+    assert_eq!(table.lookup("claude-sonnet-4-5").unwrap().input_per_mtok, 2.0);
+    assert_eq!(table.lookup("bedrock-claude-v1").unwrap().output_per_mtok, 20.0);
+    // Built-in entries survive
+    assert_eq!(table.lookup("claude-opus-4-1").unwrap().input_per_mtok, 15.0);
+
+    let only_file = PricingTable::from_file(&path).unwrap();
+    assert!(only_file.lookup("claude-opus-4-1").is_none());
+
+    std::fs::write(&path, "{ not json").unwrap();
+    match PricingTable::from_file(&path) {
+        Err(ClaudeSDKError::Config(e)) => assert_eq!(e.path.as_deref(), Some(path.as_path())),
+        other => panic!("Expected a config error, got {:?}", other),
+    }
+    assert!(PricingTable::from_file(dir.join("missing.json")).is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_result_cost_without_total_cost() {
+    let mut result = ResultMessage {
+        subtype: "success".to_string(),
+        duration_ms: 1,
+        duration_api_ms: 1,
+        is_error: false,
+        num_turns: 1,
+        session_id: "s1".to_string(),
+        total_cost_usd: None,
+        usage: Some(
+            Usage {
+                input_tokens: 1_000_000,
+                output_tokens: 1_000_000,
+                ..Usage::default()
+            }
+            .to_map(),
+        ),
+        result: None,
+        model: Some("claude-haiku-4-5-20251001".to_string()),
+        estimated_cost_usd: None,
+    };
+    let table = PricingTable::default();
+    assert_eq!(result.typed_usage().unwrap().output_tokens, 1_000_000);
+    assert_eq!(result.cost_usd(&table), Some(6.0));
+
+    // A reported cost always wins
+    result.total_cost_usd = Some(0.5);
+    assert_eq!(result.cost_usd(&table), Some(0.5));
+
+    assert!(!result.cost_is_estimate());
+
+    result.total_cost_usd = None;
+    result.estimated_cost_usd = Some(0.25);
+    assert_eq!(result.cost_usd(&table), Some(0.25));
+    assert!(result.cost_is_estimate());
+
+    result.estimated_cost_usd = None;
+    result.model = None;
+    assert_eq!(result.cost_usd(&table), None);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_query_interrupted_when_budget_exceeded() {
+    use claude_code_sdk::{query, ClaudeCodeOptions, ClaudeSDKError, Message};
+    use tokio_stream::StreamExt;
+
+    // A stand-in CLI whose second turn blows through the budget, then keeps going
+    let turn = |id: &str, output_tokens: u64| {
+        format!(
+            r#"echo '{{"type": "assistant", "message": {{"id": "{}", "model": "claude-sonnet-4-5", "role": "assistant", "content": [{{"type": "text", "text": "Working"}}], "usage": {{"input_tokens": 10, "output_tokens": {}}}}}, "session_id": "s1"}}'"#,
+            id, output_tokens
+        )
+    };
+    let (_dir, cli) = common::fake_cli(&format!(
+        "echo '{{\"type\": \"system\", \"subtype\": \"init\", \"session_id\": \"s1\"}}'\n{}\n{}\n{}\n{}\nsleep 5\necho '{{\"type\": \"result\", \"subtype\": \"success\", \"is_error\": false, \"duration_ms\": 1, \"duration_api_ms\": 1, \"num_turns\": 3, \"session_id\": \"s1\", \"result\": \"done\"}}'",
+        turn("msg_1", 10_000),
+        turn("msg_1", 10_000),
+        r#"echo '{"type": "user", "message": {"role": "user", "content": [{"type": "tool_result", "tool_use_id": "t1", "content": "ok"}]}, "session_id": "s1"}'"#,
+        turn("msg_2", 100_000),
+    ));
+
+    let options = ClaudeCodeOptions {
+        max_budget_usd: Some(1.0),
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let started = std::time::Instant::now();
+    let messages: Vec<Message> = query("Refactor everything", Some(options)).await.unwrap().collect().await;
+    assert!(started.elapsed() < std::time::Duration::from_secs(5), "The run should be interrupted");
+
+    let assistant_count = messages.iter().filter(|m| matches!(m, Message::Assistant(_))).count();
+    assert_eq!(assistant_count, 3);
+
+    let Some(Message::Result(result)) = messages.last() else {
+        panic!("Expected a result message last");
+    };
+    assert_eq!(result.subtype, "error_max_budget_usd");
+    assert_eq!(result.session_id, "s1");
+    // msg_1 answers the prompt and msg_2 the tool result
+    assert_eq!(result.num_turns, 2);
+    // msg_1 counted once: 20 input + 110k output tokens on Sonnet
+    // The SDK's estimate never poses as a billed amount
+    assert_eq!(result.total_cost_usd, None);
+    assert!(result.cost_is_estimate());
+    let spent = result.estimated_cost_usd.unwrap();
+    assert!((spent - (20.0 * 3.0 + 110_000.0 * 15.0) / 1_000_000.0).abs() < 1e-9);
+
+    match result.clone().into_result() {
+        Err(ClaudeSDKError::BudgetExceeded(e)) => assert_eq!(e.session_id(), "s1"),
+        other => panic!("Expected a budget error, got {:?}", other),
+    }
+}
@@ -0,0 +1,190 @@
+//! Tests for serializing options and loading them from profiles.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use claude_code_sdk::{
+    ClaudeCodeOptions, Ledger, McpServerConfig, PermissionMode, Profile, ProfileFormat, RetryOn, RetryPolicy,
+};
+
+mod common;
+
+fn full_options() -> ClaudeCodeOptions {
+    let mut options = ClaudeCodeOptions::builder()
+        .model("opus")
+        .fallback_model("sonnet")
+        .allowed_tools(["Read", "Grep"])
+        .permission_mode(PermissionMode::AcceptEdits)
+        .max_turns(8)
+        .max_budget_usd(1.5)
+        .retry_policy(RetryPolicy {
+            initial_backoff: Duration::from_millis(500),
+            retry_on: vec![RetryOn::Overloaded, RetryOn::Execution],
+            ..RetryPolicy::default()
+        })
+        .ledger(Ledger::new("/var/log/claude/ledger.jsonl"))
+        .mcp_server(
+            "github",
+            McpServerConfig::stdio("gh-mcp", ["--stdio"]),
+        )
+        .build()
+        .unwrap();
+    options.cwd = Some(PathBuf::from("/work/api"));
+    options
+}
+
+#[test]
+fn test_options_round_trip_every_format() {
+    let options = full_options();
+    let profile = Profile::from_options(&options);
+
+    for format in [ProfileFormat::Toml, ProfileFormat::Json, ProfileFormat::Yaml] {
+        let text = profile.serialize_as(format).unwrap();
+        let loaded = Profile::parse(&text, format).unwrap().to_options().unwrap();
+        // Options do not implement PartialEq, so compare their serialized form
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&options).unwrap(),
+            "{:?} round trip",
+            format
+        );
+    }
+
+    let json = serde_json::to_value(&options).unwrap();
+    assert_eq!(json["permission_mode"], "acceptEdits");
+    assert_eq!(json["ledger"], "/var/log/claude/ledger.jsonl");
+    assert_eq!(json["retry_policy"]["initial_backoff"], 0.5);
+    assert_eq!(json["retry_policy"]["retry_on"], serde_json::json!(["overloaded", "execution"]));
+}
+
+#[test]
+fn test_partial_profiles_and_errors() {
+    let options = Profile::parse("model = \"haiku\"\nmax_turns = 3\n", ProfileFormat::Toml)
+        .unwrap()
+        .to_options()
+        .unwrap();
+    assert_eq!(options.model.as_deref(), Some("haiku"));
+    assert_eq!(options.max_turns, Some(3));
+    assert_eq!(options.max_thinking_tokens, ClaudeCodeOptions::default().max_thinking_tokens);
+
+    // Typos are reported rather than silently ignored
+    let error = Profile::parse("{\"modle\": \"haiku\"}", ProfileFormat::Json)
+        .unwrap()
+        .to_options()
+        .unwrap_err();
+    assert!(error.message.contains("unknown field `modle`"), "{}", error);
+
+    // A nested null is kept, so profiles can remove variables from the CLI's environment
+    let options = Profile::new()
+        .with("env", serde_json::json!({"A": "1", "B": "2"}))
+        .merge(Profile::new().with("env", serde_json::json!({"B": null})))
+        .to_options()
+        .unwrap();
+    assert_eq!(options.env.get("A"), Some(&Some("1".to_string())));
+    assert_eq!(options.env.get("B"), Some(&None));
+
+    assert!(Profile::parse("- just\n- a list\n", ProfileFormat::Yaml).is_err());
+    assert!(Profile::parse("", ProfileFormat::Yaml).unwrap().values().is_empty());
+}
+
+#[test]
+fn test_layered_profiles_and_env_overrides() {
+    let dir = common::temp_dir("profile-layers");
+    let base = dir.join("base.toml");
+    std::fs::write(
+        &base,
+        r#"
+model = "sonnet"
+allowed_tools = ["Read", "Grep", "Edit"]
+max_turns = 20
+system_prompt = "You review code"
+
+[mcp_servers.github]
+command = "gh-mcp"
+
+[retry_policy]
+max_attempts = 5
+"#,
+    )
+    .unwrap();
+    let job = dir.join("job.yml");
+    std::fs::write(
+        &job,
+        r#"
+allowed_tools: [Read]
+system_prompt: null
+mcp_servers:
+  jira:
+    type: http
+    url: https://jira.example.com/mcp
+retry_policy:
+  max_backoff: 10
+"#,
+    )
+    .unwrap();
+
+    let env = [
+        ("CLAUDE_SDK_MODEL", "opus"),
+        ("CLAUDE_SDK_DISALLOWED_TOOLS", "Bash, WebFetch,"),
+        ("CLAUDE_SDK_CONTINUE_CONVERSATION", "yes"),
+        ("CLAUDE_SDK_MAX_BUDGET_USD", "0.75"),
+        ("CLAUDE_SDK_UNKNOWN", "ignored"),
+        ("PATH", "/usr/bin"),
+    ];
+    let options = Profile::from_file(&base)
+        .unwrap()
+        .merge(Profile::from_file(&job).unwrap())
+        .merge(Profile::from_vars(env).unwrap())
+        .to_options()
+        .unwrap();
+
+    assert_eq!(options.model.as_deref(), Some("opus"));
+    // Lists are replaced, tables are merged, and null resets a field
+    assert_eq!(options.allowed_tools, vec!["Read"]);
+    assert_eq!(options.disallowed_tools, vec!["Bash", "WebFetch"]);
+    assert_eq!(options.system_prompt, None);
+    assert_eq!(options.max_turns, Some(20));
+    let mut servers: Vec<_> = options.mcp_servers.keys().cloned().collect();
+    servers.sort();
+    assert_eq!(servers, vec!["github", "jira"]);
+    let policy = options.retry_policy.unwrap();
+    assert_eq!(policy.max_attempts, 5);
+    assert_eq!(policy.max_backoff, Duration::from_secs(10));
+    assert_eq!(policy.initial_backoff, RetryPolicy::default().initial_backoff);
+    assert!(options.continue_conversation);
+    assert_eq!(options.max_budget_usd, Some(0.75));
+
+    let error = Profile::from_vars([("CLAUDE_SDK_MAX_TURNS", "many")]).unwrap_err();
+    assert_eq!(error.message, "CLAUDE_SDK_MAX_TURNS must be a whole number, got \"many\"");
+
+    let error = Profile::from_file(dir.join("profile.ini")).unwrap_err();
+    assert!(error.message.contains("Unknown profile format"));
+    assert_eq!(error.path, Some(dir.join("profile.ini")));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_override_replaces_mcp_server_entries() {
+    let base = Profile::parse(
+        r#"
+[mcp_servers.github]
+command = "gh-mcp"
+args = ["--stdio"]
+
+[mcp_servers.jira]
+command = "jira-mcp"
+"#,
+        ProfileFormat::Toml,
+    )
+    .unwrap();
+    let job = Profile::parse(
+        r#"{"mcp_servers": {"github": {"type": "http", "url": "https://mcp.github.example/mcp"}}}"#,
+        ProfileFormat::Json,
+    )
+    .unwrap();
+
+    let options = base.merge(job).to_options().unwrap();
+    assert_eq!(options.mcp_servers["github"], McpServerConfig::http("https://mcp.github.example/mcp"));
+    assert_eq!(options.mcp_servers["jira"], McpServerConfig::stdio("jira-mcp", Vec::<String>::new()));
+}
@@ -0,0 +1,165 @@
+//! Tests for rendering conversations as Markdown and HTML.
+
+use std::collections::HashMap;
+
+use claude_code_sdk::{
+    render_html, render_markdown, AssistantMessage, ContentBlock, Message, RenderOptions, ResultMessage,
+    SystemMessage, TextBlock, ToolResultBlock, ToolResultContent, ToolUseBlock, Transcript,
+};
+use serde_json::json;
+
+fn tool_use(id: &str, name: &str, input: serde_json::Value) -> ContentBlock {
+    ContentBlock::ToolUse(ToolUseBlock {
+        id: id.to_string(),
+        name: name.to_string(),
+        input: serde_json::from_value(input).unwrap(),
+    })
+}
+
+fn conversation() -> Vec<Message> {
+    let mut init = HashMap::new();
+    init.insert("model".to_string(), json!("claude-sonnet-4-5"));
+    let usage: HashMap<String, serde_json::Value> =
+        serde_json::from_value(json!({"input_tokens": 1000, "output_tokens": 200})).unwrap();
+
+    vec![
+        Message::System(SystemMessage {
+            subtype: "init".to_string(),
+            data: init,
+        }),
+        Message::User(claude_code_sdk::UserMessage {
+            content: "Rename <Widget> in src/lib.rs".to_string(),
+        }),
+        Message::Assistant(AssistantMessage {
+            content: vec![
+                ContentBlock::Text(TextBlock {
+                    text: "Renaming it now.".to_string(),
+                }),
+                tool_use(
+                    "toolu_1",
+                    "Edit",
+                    json!({
+                        "file_path": "src/lib.rs",
+                        "old_string": "use std::fmt;\nstruct Widget;\n",
+                        "new_string": "use std::fmt;\nstruct Gadget;\n",
+                    }),
+                ),
+            ],
+        }),
+        Message::Assistant(AssistantMessage {
+            content: vec![
+                ContentBlock::ToolResult(ToolResultBlock {
+                    tool_use_id: "toolu_1".to_string(),
+                    content: Some(ToolResultContent::Text("x".repeat(50))),
+                    is_error: None,
+                }),
+                tool_use(
+                    "toolu_2",
+                    "MultiEdit",
+                    json!({
+                        "file_path": "src/main.rs",
+                        "edits": [
+                            {"old_string": "Widget::new()", "new_string": "Gadget::new()"},
+                            {"old_string": "let w = 1;", "new_string": "let g = 1;"},
+                        ],
+                    }),
+                ),
+                tool_use("toolu_3", "Bash", json!({"command": "cargo test"})),
+                ContentBlock::ToolResult(ToolResultBlock {
+                    tool_use_id: "toolu_3".to_string(),
+                    content: Some(ToolResultContent::Text("```E0425```".to_string())),
+                    is_error: Some(true),
+                }),
+            ],
+        }),
+        Message::Result(ResultMessage {
+            subtype: "success".to_string(),
+            duration_ms: 12_300,
+            duration_api_ms: 10_000,
+            is_error: false,
+            num_turns: 3,
+            session_id: "abc".to_string(),
+            total_cost_usd: None,
+            usage: Some(usage),
+            result: Some("Renamed".to_string()),
+            model: None,
+            estimated_cost_usd: None,
+        }),
+    ]
+}
+
+fn options() -> RenderOptions {
+    RenderOptions {
+        max_tool_result_chars: 20,
+        ..RenderOptions::with_title("Rename run")
+    }
+}
+
+#[test]
+fn test_render_markdown() {
+    let markdown = render_markdown(&conversation(), &options());
+
+    assert!(markdown.starts_with("# Rename run\n\n## User\n\nRename <Widget> in src/lib.rs\n\n## Assistant\n\nRenaming it now.\n"));
+    // Consecutive assistant messages share one heading
+    assert_eq!(markdown.matches("## Assistant").count(), 1);
+
+    // Edits become diffs that keep the unchanged lines as context
+    assert!(markdown.contains("<summary><code>Edit</code> src/lib.rs</summary>"));
+    assert!(markdown.contains("```diff\n use std::fmt;\n-struct Widget;\n+struct Gadget;\n```"));
+    assert!(markdown.contains("```diff\n-let w = 1;\n+let g = 1;\n```"));
+
+    // Tool output is attached to its call and truncated
+    assert!(markdown.contains(&format!("**Output**\n\n```\n{}\n… 30 more characters truncated\n```", "x".repeat(20))));
+
+    // Fences grow past backticks in the content, and errors are flagged
+    assert!(markdown.contains("<summary><code>Bash</code> cargo test (error)</summary>"));
+    assert!(markdown.contains("```json\n{\n  \"command\": \"cargo test\"\n}\n```"));
+    assert!(markdown.contains("**Error**\n\n````\n```E0425```\n````"));
+
+    // The footer prices the usage for the model from the init message
+    let cost = (1000.0 * 3.0 + 200.0 * 15.0) / 1_000_000.0;
+    assert!(markdown.ends_with(&format!(
+        "---\n\n**Result:** success · claude-sonnet-4-5 · 3 turns · 12.3s · ${:.4} · 1000 input tokens · 200 output tokens\n",
+        cost
+    )));
+}
+
+#[test]
+fn test_render_html() {
+    let html = render_html(&conversation(), &options());
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Rename run</title>"));
+    assert!(html.contains("<style>"));
+    assert!(!html.contains("<script") && !html.contains("<link"));
+
+    assert!(html.contains("<div class=\"text\">Rename &lt;Widget&gt; in src/lib.rs</div>"));
+    assert!(html.contains("<details class=\"tool\">\n<summary><code>Edit</code> src/lib.rs</summary>"));
+    assert!(html.contains(
+        "<pre class=\"diff\"><span> use std::fmt;</span><span class=\"del\">-struct Widget;</span><span class=\"add\">+struct Gadget;</span></pre>"
+    ));
+    assert!(html.contains("<details class=\"tool error\">"));
+    assert!(html.contains("<footer><strong>Result:</strong> success · claude-sonnet-4-5 · 3 turns"));
+    assert!(html.trim_end().ends_with("</html>"));
+}
+
+#[test]
+fn test_render_transcript() {
+    let path = std::env::temp_dir().join(format!("claude-sdk-test-render-{}.jsonl", std::process::id()));
+    std::fs::write(&path, include_str!("fixtures/session.jsonl")).unwrap();
+    let transcript = Transcript::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let markdown = transcript.render_markdown(&RenderOptions::default());
+    assert!(markdown.starts_with("# Fix the users table migration\n\n## User\n\nFix the failing migration\n"));
+    // Tool output comes from the raw transcript lines
+    assert!(markdown.contains("<summary><code>Read</code> /work/api/migrations/001_users.sql</summary>"));
+    assert!(markdown.contains("**Output**\n\n```\nCREATE TABLE users (id int);\n```"));
+    // Subagent prompts stay out of the main conversation
+    assert!(!markdown.contains("Search for users references"));
+    assert!(markdown.ends_with("The migration is fixed.\n"));
+
+    let html = transcript.render_html(&RenderOptions::with_title("Incident 42"));
+    assert!(html.contains("<h1>Incident 42</h1>"));
+    assert!(html.contains("CREATE TABLE users (id int);"));
+}
@@ -0,0 +1,157 @@
+//! Tests for retry policies.
+
+use std::time::Duration;
+
+use claude_code_sdk::{ApiError, ClaudeSDKError, RetryOn, RetryPolicy};
+
+mod common;
+
+#[test]
+fn test_retry_policy_default() {
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.max_attempts, 3);
+    assert_eq!(policy.retry_on, vec![RetryOn::Overloaded, RetryOn::RateLimited]);
+
+    let none = RetryPolicy::none();
+    assert_eq!(none.max_attempts, 1);
+    assert!(none.retry_on.is_empty());
+}
+
+#[test]
+fn test_is_retryable() {
+    let policy = RetryPolicy::default();
+    assert!(policy.is_retryable(&ClaudeSDKError::Overloaded(ApiError::new("Overloaded"))));
+    assert!(policy.is_retryable(&ClaudeSDKError::RateLimited(ApiError::new("Slow down"))));
+    assert!(!policy.is_retryable(&ClaudeSDKError::Authentication(ApiError::new("Bad key"))));
+    assert!(!policy.is_retryable(&ClaudeSDKError::Other("boom".to_string())));
+
+    let policy = RetryPolicy {
+        retry_on: vec![RetryOn::Execution],
+        ..RetryPolicy::default()
+    };
+    assert!(!policy.is_retryable(&ClaudeSDKError::Overloaded(ApiError::new("Overloaded"))));
+    assert!(policy.is_retryable(&ClaudeSDKError::Io(std::io::Error::other("broken pipe"))));
+}
+
+#[test]
+fn test_backoff_growth_and_cap() {
+    let policy = RetryPolicy {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(350),
+        backoff_multiplier: 2.0,
+        jitter: 0.0,
+        ..RetryPolicy::default()
+    };
+
+    assert_eq!(policy.backoff(1, None), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2, None), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3, None), Duration::from_millis(350));
+    assert_eq!(policy.backoff(30, None), Duration::from_millis(350));
+
+    // A longer retry-after hint wins over the computed delay
+    assert_eq!(policy.backoff(1, Some(Duration::from_secs(5))), Duration::from_secs(5));
+    assert_eq!(policy.backoff(2, Some(Duration::from_millis(10))), Duration::from_millis(200));
+}
+
+#[test]
+fn test_backoff_jitter_bounds() {
+    let policy = RetryPolicy {
+        initial_backoff: Duration::from_millis(1000),
+        jitter: 0.5,
+        ..RetryPolicy::default()
+    };
+
+    for _ in 0..100 {
+        let delay = policy.backoff(1, None);
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_millis(1500));
+    }
+}
+
+#[test]
+fn test_backoff_with_unbounded_cap() {
+    // Jitter can push the delay past what a Duration can hold
+    let policy = RetryPolicy {
+        initial_backoff: Duration::MAX,
+        max_backoff: Duration::MAX,
+        jitter: 1.0,
+        ..RetryPolicy::default()
+    };
+    for attempt in 1..5 {
+        assert!(policy.backoff(attempt, None) <= Duration::MAX);
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_query_retries_and_resumes_session() {
+    use claude_code_sdk::{query, ClaudeCodeOptions, Message};
+    use tokio_stream::StreamExt;
+
+    // A stand-in CLI that is overloaded on its first run and succeeds afterwards
+    let (dir, cli) = common::fake_cli(
+        r#"if [ $n -eq 1 ]; then
+  echo '{"type": "system", "subtype": "init", "session_id": "s1"}'
+  echo '{"type": "result", "subtype": "success", "is_error": true, "duration_ms": 1, "duration_api_ms": 1, "num_turns": 1, "session_id": "s1", "result": "API Error: 529 Overloaded"}'
+else
+  echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 1, "duration_api_ms": 1, "num_turns": 2, "session_id": "s1", "result": "done"}'
+fi"#,
+    );
+
+    let options = ClaudeCodeOptions {
+        retry_policy: Some(RetryPolicy {
+            initial_backoff: Duration::from_millis(10),
+            ..RetryPolicy::default()
+        }),
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let messages: Vec<Message> = query("Fix the migration", Some(options)).await.unwrap().collect().await;
+
+    let results: Vec<_> = messages
+        .iter()
+        .filter_map(|m| match m {
+            Message::Result(result) => Some(result),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(results.len(), 1, "The failed attempt's result should be hidden");
+    assert!(!results[0].is_error);
+    assert_eq!(results[0].result.as_deref(), Some("done"));
+
+    let retry_args = std::fs::read_to_string(dir.path().join("args_2")).unwrap();
+    assert!(retry_args.contains("--resume s1"));
+    assert!(retry_args.contains("Continue from where you left off."));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_query_retries_api_error_from_stderr() {
+    use claude_code_sdk::{query, ClaudeCodeOptions, Message};
+    use tokio_stream::StreamExt;
+
+    // The first run dies before producing a result, leaving only stderr
+    let (dir, cli) = common::fake_cli(
+        r#"if [ $n -eq 1 ]; then
+  echo 'Starting session' >&2
+  echo 'API Error: 529 {"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}' >&2
+  exit 1
+fi
+echo '{"type": "result", "subtype": "success", "is_error": false, "duration_ms": 1, "duration_api_ms": 1, "num_turns": 1, "session_id": "s1", "result": "done"}'"#,
+    );
+
+    let options = ClaudeCodeOptions {
+        retry_policy: Some(RetryPolicy {
+            initial_backoff: Duration::from_millis(10),
+            ..RetryPolicy::default()
+        }),
+        cli_path: Some(cli),
+        ..Default::default()
+    };
+    let messages: Vec<Message> = query("Fix the migration", Some(options)).await.unwrap().collect().await;
+
+    match messages.last() {
+        Some(Message::Result(result)) => assert_eq!(result.result.as_deref(), Some("done")),
+        other => panic!("Expected the retried run's result, got {:?}", other),
+    }
+    assert!(dir.path().join("args_2").exists(), "The overloaded run should be retried");
+}
@@ -10,6 +10,7 @@ fn test_safety_limits_default() {
     assert_eq!(limits.max_log_preview_chars, 200);
     assert_eq!(limits.max_buffered_messages, 100);
     assert_eq!(limits.json_parse_timeout_ms, 5000);
+    assert_eq!(limits.max_json_lines, 100_000);
 }
 
 #[test]
@@ -22,6 +23,7 @@ fn test_safety_limits_conservative() {
     assert_eq!(limits.max_log_preview_chars, 100);
     assert_eq!(limits.max_buffered_messages, 50);
     assert_eq!(limits.json_parse_timeout_ms, 2000);
+    assert_eq!(limits.max_json_lines, 10_000);
 }
 
 #[test]
@@ -34,6 +36,7 @@ fn test_safety_limits_generous() {
     assert_eq!(limits.max_log_preview_chars, 500);
     assert_eq!(limits.max_buffered_messages, 200);
     assert_eq!(limits.json_parse_timeout_ms, 10000);
+    assert_eq!(limits.max_json_lines, 1_000_000);
 }
 
 #[test]
@@ -163,6 +166,7 @@ fn test_custom_safety_limits() {
         max_log_preview_chars: 50,
         max_buffered_messages: 10,
         json_parse_timeout_ms: 1000,
+        max_json_lines: 100,
     };
     
     // Test line size checks
@@ -222,6 +226,7 @@ fn test_zero_limits_edge_case() {
         max_log_preview_chars: 0,
         max_buffered_messages: 0,
         json_parse_timeout_ms: 0,
+        max_json_lines: 0,
     };
     
     // Everything should be unsafe with zero limits
@@ -0,0 +1,388 @@
+//! Tests for reading stored session transcripts.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use claude_code_sdk::sessions::{encode_project_dir, read_bundle_manifest, PassageKind};
+use claude_code_sdk::{
+    ClaudeCodeOptions, ContentBlock, Message, PermissionMode, RetentionPolicy, SearchIndex, SessionStore, Transcript,
+};
+
+mod common;
+
+const FIXTURE: &str = include_str!("fixtures/session.jsonl");
+
+/// A config dir holding the fixture session for `/work/api` and a short one for `/work/web`
+fn config_dir(name: &str) -> PathBuf {
+    let dir = common::temp_dir(&format!("sessions-{}", name));
+    let store = SessionStore::new(&dir);
+
+    let api = store.project_dir(Path::new("/work/api"));
+    std::fs::create_dir_all(&api).unwrap();
+    std::fs::write(api.join("3f1c.jsonl"), FIXTURE).unwrap();
+    std::fs::write(api.join("notes.txt"), "not a session").unwrap();
+
+    let web = store.project_dir(Path::new("/work/web"));
+    std::fs::create_dir_all(&web).unwrap();
+    std::fs::write(
+        web.join("9a2b.jsonl"),
+        r#"{"type":"user","cwd":"/work/web","message":{"role":"user","content":"Add a button"},"uuid":"w1","timestamp":"2025-03-02T09:00:00.000Z"}
+"#,
+    )
+    .unwrap();
+    dir
+}
+
+#[test]
+fn test_encode_project_dir() {
+    assert_eq!(encode_project_dir(Path::new("/work/api")), "-work-api");
+    assert_eq!(encode_project_dir(Path::new("/Users/me/my_app.v2")), "-Users-me-my-app-v2");
+}
+
+#[test]
+fn test_transcript_metadata() {
+    let dir = config_dir("metadata");
+    let store = SessionStore::new(&dir);
+    let transcript = store.read_session("3f1c").unwrap();
+    let info = &transcript.info;
+
+    assert_eq!(info.session_id, "3f1c");
+    assert_eq!(info.cwd.as_deref(), Some(Path::new("/work/api")));
+    assert_eq!(info.first_prompt.as_deref(), Some("Fix the failing migration"));
+    assert_eq!(info.summary.as_deref(), Some("Fix the users table migration"));
+    assert_eq!(info.git_branch.as_deref(), Some("fix-migration"));
+    assert_eq!(info.started_at.unwrap().to_rfc3339(), "2025-03-01T10:00:00+00:00");
+    assert_eq!(info.updated_at.unwrap().to_rfc3339(), "2025-03-01T10:01:00+00:00");
+    // Two user prompts are skipped: a tool result and a subagent prompt
+    assert_eq!(info.message_count, 4);
+    // msg_1 is counted once: 3000 input and 300 output tokens on Sonnet
+    let cost = info.last_cost_usd.unwrap();
+    assert!((cost - (3000.0 * 3.0 + 300.0 * 15.0) / 1_000_000.0).abs() < 1e-9);
+    assert_eq!(info.size_bytes, FIXTURE.len() as u64);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_transcript_messages() {
+    let dir = config_dir("messages");
+    let transcript = Transcript::read(dir.join("projects/-work-api/3f1c.jsonl")).unwrap();
+    assert_eq!(transcript.entries.len(), 7);
+
+    let messages = transcript.messages();
+    assert_eq!(messages.len(), 4);
+    assert!(matches!(&messages[0], Message::User(user) if user.content == "Fix the failing migration"));
+    match &messages[2] {
+        Message::Assistant(assistant) => {
+            assert!(matches!(&assistant.content[0], ContentBlock::ToolUse(tool) if tool.name == "Read"));
+        }
+        other => panic!("Expected an assistant message, got {:?}", other),
+    }
+
+    // The tool result is kept in its raw form
+    let tool_result = &transcript.entries[4];
+    assert!(tool_result.message.is_none());
+    assert_eq!(tool_result.entry_type(), Some("user"));
+    assert_eq!(tool_result.parent_uuid.as_deref(), Some("u3"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_list_sessions() {
+    let dir = config_dir("list");
+    let store = SessionStore::new(&dir);
+
+    let api_sessions = store.list_sessions(Path::new("/work/api")).unwrap();
+    assert_eq!(api_sessions.len(), 1);
+    assert_eq!(api_sessions[0].session_id, "3f1c");
+
+    let all = store.list_all_sessions().unwrap();
+    let ids: Vec<_> = all.iter().map(|s| s.session_id.as_str()).collect();
+    assert_eq!(ids, vec!["9a2b", "3f1c"], "Most recently updated first");
+    assert_eq!(store.project_dirs().unwrap().len(), 2);
+
+    assert!(store.list_sessions(Path::new("/work/none")).unwrap().is_empty());
+    assert!(store.find_session("missing").unwrap().is_none());
+    assert!(store.read_session("missing").is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Write an empty-ish session modified `age_days` ago
+fn write_aged_session(store: &SessionStore, cwd: &Path, session_id: &str, age_days: u64) {
+    let path = store.session_path(cwd, session_id).unwrap();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"hi\"}}\n").unwrap();
+    let modified = std::time::SystemTime::now() - Duration::from_secs(age_days * 24 * 60 * 60);
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+}
+
+#[test]
+fn test_delete_session() {
+    let dir = config_dir("delete");
+    let store = SessionStore::new(&dir);
+    let side_dir = store.project_dir(Path::new("/work/api")).join("3f1c");
+    std::fs::create_dir_all(side_dir.join("tool-results")).unwrap();
+    std::fs::write(side_dir.join("tool-results/1.txt"), "output").unwrap();
+
+    assert!(store.delete_session("3f1c").unwrap());
+    assert!(store.find_session("3f1c").unwrap().is_none());
+    assert!(!side_dir.exists());
+    assert!(!store.delete_session("3f1c").unwrap());
+    assert!(store.find_session("9a2b").unwrap().is_some());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_session_ids_cannot_leave_projects_dir() {
+    let dir = config_dir("traversal");
+    let store = SessionStore::new(&dir);
+    std::fs::write(dir.join("victim.jsonl"), "{}\n").unwrap();
+    std::fs::create_dir_all(dir.join("victim")).unwrap();
+    std::fs::write(dir.join("x.jsonl"), "{}\n").unwrap();
+    let outside = dir.join("outside.jsonl");
+    std::fs::write(&outside, "{}\n").unwrap();
+    let absolute = outside.with_extension("").to_string_lossy().into_owned();
+
+    for id in ["../x", "../../victim", "a/b", "..", ".", "", absolute.as_str()] {
+        assert!(store.delete_session(id).is_err(), "{:?} should be rejected", id);
+        assert!(store.find_session(id).is_err(), "{:?} should be rejected", id);
+        assert!(store.read_session(id).is_err(), "{:?} should be rejected", id);
+        assert!(store.session_path(Path::new("/work/api"), id).is_err(), "{:?} should be rejected", id);
+    }
+    assert!(dir.join("victim.jsonl").exists());
+    assert!(dir.join("victim").is_dir());
+    assert!(dir.join("x.jsonl").exists());
+    assert!(outside.exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_prune_by_age_and_count() {
+    let dir = common::temp_dir("sessions-prune");
+    let store = SessionStore::new(&dir);
+    let api = Path::new("/work/api");
+    let web = Path::new("/work/web");
+    for (id, age) in [("a1", 1), ("a2", 2), ("a3", 3), ("a4", 40)] {
+        write_aged_session(&store, api, id, age);
+    }
+    write_aged_session(&store, web, "w1", 50);
+
+    let usage = store.disk_usage().unwrap();
+    assert_eq!(usage.session_count, 5);
+    assert_eq!(usage.by_project.len(), 2);
+    assert_eq!(usage.total_bytes, usage.by_project.values().sum::<u64>());
+
+    let dry_run = RetentionPolicy {
+        dry_run: true,
+        ..RetentionPolicy::max_age(Duration::from_secs(30 * 24 * 60 * 60))
+    };
+    let report = store.prune(&dry_run).unwrap();
+    assert_eq!(report.deleted.len(), 2);
+    assert!(report.dry_run);
+    assert_eq!(store.disk_usage().unwrap().session_count, 5, "A dry run deletes nothing");
+
+    let report = store.prune_project(api, &RetentionPolicy::keep_latest(2)).unwrap();
+    let mut deleted: Vec<_> = report.deleted.iter().map(|s| s.session_id.as_str()).collect();
+    deleted.sort();
+    assert_eq!(deleted, vec!["a3", "a4"]);
+    assert_eq!(report.kept, 2);
+    assert!(report.freed_bytes > 0);
+    assert!(store.find_session("w1").unwrap().is_some(), "Other projects are untouched");
+
+    let report = store.prune(&RetentionPolicy::max_age(Duration::from_secs(30 * 24 * 60 * 60))).unwrap();
+    assert_eq!(report.deleted.len(), 1);
+    assert_eq!(report.deleted[0].session_id, "w1");
+    assert_eq!(store.disk_usage().unwrap().session_count, 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_export_and_import_bundle() {
+    let source_dir = config_dir("export");
+    let source = SessionStore::new(&source_dir);
+    let side_dir = source.project_dir(Path::new("/work/api")).join("3f1c");
+    std::fs::create_dir_all(side_dir.join("tool-results")).unwrap();
+    std::fs::write(side_dir.join("tool-results/1.txt"), "output").unwrap();
+
+    let archive = source_dir.join("3f1c.tar.gz");
+    let options = ClaudeCodeOptions {
+        model: Some("claude-sonnet-4-5".to_string()),
+        allowed_tools: vec!["Read".to_string(), "Edit".to_string()],
+        permission_mode: Some(PermissionMode::AcceptEdits),
+        cwd: Some(PathBuf::from("/work/api")),
+        ..Default::default()
+    };
+    let manifest = source.export_session("3f1c", &archive, Some(&options)).unwrap();
+    assert_eq!(manifest.cwd.as_deref(), Some(Path::new("/work/api")));
+    assert_eq!(read_bundle_manifest(&archive).unwrap(), manifest);
+
+    let target_dir = common::temp_dir("sessions-import");
+    let target = SessionStore::new(&target_dir);
+    let target_cwd = Path::new("/home/dev/src/api");
+    let imported = target.import_session(&archive, target_cwd).unwrap();
+
+    assert_eq!(imported.transcript_path, target.session_path(target_cwd, "3f1c").unwrap());
+    assert_eq!(imported.options.resume.as_deref(), Some("3f1c"));
+    assert_eq!(imported.options.cwd.as_deref(), Some(target_cwd));
+    assert_eq!(imported.options.model.as_deref(), Some("claude-sonnet-4-5"));
+    assert_eq!(imported.options.allowed_tools, vec!["Read", "Edit"]);
+    assert_eq!(imported.options.permission_mode, Some(PermissionMode::AcceptEdits));
+
+    let transcript = target.read_session("3f1c").unwrap();
+    assert_eq!(transcript.info.cwd.as_deref(), Some(target_cwd));
+    assert_eq!(transcript.info.message_count, 4);
+    let read = transcript.entries[3].raw["message"]["content"][0]["input"]["file_path"].as_str();
+    assert_eq!(read, Some("/home/dev/src/api/migrations/001_users.sql"));
+    let side_file = imported.transcript_path.with_extension("").join("tool-results/1.txt");
+    assert_eq!(std::fs::read_to_string(side_file).unwrap(), "output");
+
+    // Importing twice would clobber the session
+    assert!(target.import_session(&archive, target_cwd).is_err());
+
+    std::fs::remove_dir_all(&source_dir).unwrap();
+    std::fs::remove_dir_all(&target_dir).unwrap();
+}
+
+/// Export the fixture session, then repack it with a different manifest cwd and transcript
+fn crafted_bundle(name: &str, cwd: &str, transcript: &str) -> (PathBuf, PathBuf) {
+    let dir = config_dir(name);
+    let exported = dir.join("exported.tar.gz");
+    let mut manifest = SessionStore::new(&dir).export_session("3f1c", &exported, None).unwrap();
+    manifest.cwd = Some(PathBuf::from(cwd));
+
+    let archive = dir.join("crafted.tar.gz");
+    let gz = flate2::write::GzEncoder::new(std::fs::File::create(&archive).unwrap(), flate2::Compression::default());
+    let mut builder = tar::Builder::new(gz);
+    for (path, contents) in [
+        ("manifest.json", serde_json::to_vec(&manifest).unwrap()),
+        ("transcript.jsonl", transcript.as_bytes().to_vec()),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, contents.as_slice()).unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap();
+    (dir, archive)
+}
+
+#[test]
+fn test_import_rejects_unusable_bundle_cwd() {
+    for (name, cwd) in [("empty-cwd", ""), ("root-cwd", "/"), ("relative-cwd", "work/api")] {
+        let (dir, archive) = crafted_bundle(name, cwd, FIXTURE);
+        assert!(read_bundle_manifest(&archive).is_err(), "cwd {:?} was accepted", cwd);
+
+        let target = SessionStore::new(dir.join("target"));
+        let err = target.import_session(&archive, Path::new("/home/dev/src/api")).unwrap_err();
+        assert!(err.to_string().contains("invalid working directory"), "{}", err);
+        assert!(!target.project_dir(Path::new("/home/dev/src/api")).exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn test_import_rewrites_only_whole_paths() {
+    let transcript = r#"{"type":"user","cwd":"/work/api","message":{"role":"user","content":"cd /work/api/src && diff /work/api /x/work/api /work/apis"},"uuid":"u1","timestamp":"2025-03-01T10:00:00.000Z"}
+"#;
+    let (dir, archive) = crafted_bundle("whole-paths", "/work/api", transcript);
+    let target = SessionStore::new(dir.join("target"));
+    let target_cwd = Path::new("/home/dev/src/api");
+    target.import_session(&archive, target_cwd).unwrap();
+
+    let transcript = target.read_session("3f1c").unwrap();
+    assert_eq!(transcript.info.cwd.as_deref(), Some(target_cwd));
+    assert_eq!(
+        transcript.entries[0].raw["message"]["content"],
+        "cd /home/dev/src/api/src && diff /home/dev/src/api /x/work/api /work/apis"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_search_index() {
+    let dir = config_dir("search");
+    let store = SessionStore::new(&dir);
+    let mut index = SearchIndex::new();
+
+    let update = index.update(&store).unwrap();
+    assert_eq!((update.added, update.updated, update.removed, update.unchanged), (2, 0, 0, 0));
+
+    // Prefixes match, and every term has to be present
+    let hits = index.search("MIGRATION users", 10);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].session_id, "3f1c");
+    assert_eq!(hits[0].cwd.as_deref(), Some(Path::new("/work/api")));
+    assert_eq!(hits[0].snippets[0].kind, PassageKind::Summary);
+    assert_eq!(hits[0].snippets[0].text, "Fix the users table migration");
+
+    // Tool names and touched files are indexed, tool output is not
+    let hits = index.search("001_users.sql", 10);
+    assert_eq!(hits[0].snippets[0].kind, PassageKind::FilePath);
+    assert_eq!(index.search("read", 10).len(), 1);
+    assert!(index.search("create table", 10).is_empty());
+    assert!(index.search("migration button", 10).is_empty());
+    assert_eq!(index.search("button", 10)[0].session_id, "9a2b");
+
+    // A saved index only re-reads sessions that changed
+    let index_path = dir.join("search-index.json");
+    index.save(&index_path).unwrap();
+    let mut index = SearchIndex::load(&index_path).unwrap();
+    assert_eq!(index.len(), 2);
+
+    let web = store.project_dir(Path::new("/work/web"));
+    std::fs::write(
+        web.join("9a2b.jsonl"),
+        r#"{"type":"user","cwd":"/work/web","message":{"role":"user","content":"Add a dropdown"},"uuid":"w1","timestamp":"2025-03-02T09:00:00.000Z"}
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        web.join("c4d5.jsonl"),
+        r#"{"type":"user","cwd":"/work/web","message":{"role":"user","content":"Style the dropdown"},"uuid":"w2","timestamp":"2025-03-03T09:00:00.000Z"}
+"#,
+    )
+    .unwrap();
+    assert!(store.delete_session("3f1c").unwrap());
+
+    let update = index.update(&store).unwrap();
+    assert_eq!((update.added, update.updated, update.removed, update.unchanged), (1, 1, 1, 0));
+    assert!(index.search("button", 10).is_empty());
+    assert!(index.search("migration", 10).is_empty());
+    assert_eq!(index.search("dropdown", 10).len(), 2);
+    assert_eq!(index.search("dropdown", 1).len(), 1);
+
+    let update = index.update(&store).unwrap();
+    assert_eq!(update.unchanged, 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_search_snippets_with_case_changing_characters() {
+    let dir = common::temp_dir("sessions-unicode");
+    let store = SessionStore::new(&dir);
+    let project = store.project_dir(Path::new("/work/api"));
+    std::fs::create_dir_all(&project).unwrap();
+    // `İ` lowercases to two characters and the Kelvin sign `K` to a shorter `k`
+    std::fs::write(
+        project.join("u1.jsonl"),
+        "{\"type\":\"user\",\"cwd\":\"/work/api\",\"message\":{\"role\":\"user\",\"content\":\"\u{130}\u{130} b\u{212A}\"},\"uuid\":\"u1\"}\n",
+    )
+    .unwrap();
+
+    let mut index = SearchIndex::new();
+    index.update(&store).unwrap();
+    let hits = index.search("b", 5);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].snippets[0].text, "\u{130}\u{130} b\u{212A}");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,130 @@
+//! Tests for the subprocess transport against a stand-in CLI script.
+
+#![cfg(unix)]
+
+use claude_code_sdk::transport::subprocess_cli::SubprocessCLITransport;
+use claude_code_sdk::transport::Transport;
+use claude_code_sdk::ClaudeCodeOptions;
+use tokio_stream::StreamExt;
+
+mod common;
+
+#[tokio::test]
+async fn test_stderr_is_captured() {
+    let (_dir, cli) = common::fake_cli(
+        r#"echo '{"type": "system", "subtype": "init"}'
+echo 'Invalid API key · Please run /login' >&2
+exit 1"#,
+    );
+    let mut transport = SubprocessCLITransport::new("test", ClaudeCodeOptions::default(), cli.to_str())
+        .expect("Should create transport");
+
+    transport.connect().await.expect("Should start fake CLI");
+    let messages: Vec<_> = transport.receive_messages().collect().await;
+    assert_eq!(messages.len(), 1);
+
+    let stderr = transport.captured_stderr().await;
+    assert!(stderr.contains("Invalid API key"));
+    transport.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unknown_user_fails_to_connect() {
+    let (_dir, cli) = common::fake_cli("exit 0");
+    let options = ClaudeCodeOptions {
+        user: Some("no-such-user-for-claude-sdk".to_string()),
+        ..ClaudeCodeOptions::default()
+    };
+    let mut transport = SubprocessCLITransport::new("Hello", options, Some(cli.to_str().unwrap())).unwrap();
+    let error = transport.connect().await.unwrap_err();
+    assert!(error.to_string().contains("Unknown user \"no-such-user-for-claude-sdk\""), "{}", error);
+}
+
+#[tokio::test]
+async fn test_uid_without_passwd_entry_fails_to_connect() {
+    let (_dir, cli) = common::fake_cli("exit 0");
+    let options = ClaudeCodeOptions {
+        user: Some("4000000000".to_string()),
+        ..ClaudeCodeOptions::default()
+    };
+    let mut transport = SubprocessCLITransport::new("Hello", options, Some(cli.to_str().unwrap())).unwrap();
+    let error = transport.connect().await.unwrap_err();
+    assert!(error.to_string().contains("Unknown user \"4000000000\""), "{}", error);
+}
+
+#[tokio::test]
+async fn test_user_sets_identity_variables() {
+    use std::os::unix::fs::MetadataExt;
+
+    // Switching to our own uid needs no privileges
+    let uid = std::fs::metadata(tempfile::tempdir().unwrap().path()).unwrap().uid().to_string();
+    let passwd = std::fs::read_to_string("/etc/passwd").unwrap();
+    let Some(fields) = passwd
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.get(2) == Some(&uid.as_str()))
+    else {
+        return;
+    };
+
+    let mut options = ClaudeCodeOptions {
+        user: Some(uid),
+        ..ClaudeCodeOptions::default()
+    };
+    options.env.insert("HOME".to_string(), Some("/srv/claude-home".to_string()));
+    let env = cli_env(options).await;
+    assert_eq!(env["USER"], fields[0]);
+    assert_eq!(env["LOGNAME"], fields[0]);
+    // Explicit variables win over the passwd entry
+    assert_eq!(env["HOME"], "/srv/claude-home");
+}
+
+/// Run a fake CLI that dumps its environment and return the variables it saw
+async fn cli_env(options: ClaudeCodeOptions) -> std::collections::BTreeMap<String, String> {
+    let (dir, cli) = common::fake_cli(r#"/usr/bin/env > "$dir/env.txt""#);
+
+    let mut transport = SubprocessCLITransport::new("Hello", options, Some(cli.to_str().unwrap())).unwrap();
+    transport.connect().await.unwrap();
+    let _: Vec<_> = transport.receive_messages().collect().await;
+    transport.disconnect().await.unwrap();
+
+    std::fs::read_to_string(dir.path().join("env.txt"))
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_env_overrides_and_removals() {
+    let options = ClaudeCodeOptions::builder()
+        .env("CLAUDE_SDK_TEST_FOO", "bar")
+        .remove_env("HOME")
+        .build()
+        .unwrap();
+    let vars = cli_env(options).await;
+
+    assert_eq!(vars.get("CLAUDE_SDK_TEST_FOO").map(String::as_str), Some("bar"));
+    assert_eq!(vars.get("CLAUDE_CODE_ENTRYPOINT").map(String::as_str), Some("sdk-rust"));
+    assert!(!vars.contains_key("HOME"));
+    assert_eq!(vars.get("PATH"), std::env::var("PATH").ok().as_ref());
+    // The SDK never touches its own process environment
+    assert!(std::env::var("CLAUDE_SDK_TEST_FOO").is_err());
+    assert!(std::env::var("HOME").is_ok());
+}
+
+#[tokio::test]
+async fn test_env_clear_with_allowlist() {
+    let options = ClaudeCodeOptions::builder()
+        .env_clear(["PAT*"])
+        .env("CLAUDE_SDK_TEST_FOO", "bar")
+        .build()
+        .unwrap();
+    let vars = cli_env(options).await;
+
+    let mut names: Vec<&str> = vars.keys().map(String::as_str).collect();
+    // The shell may define PWD and similar on its own
+    names.retain(|name| !["PWD", "SHLVL", "_", "OLDPWD"].contains(name));
+    assert_eq!(names, vec!["CLAUDE_CODE_ENTRYPOINT", "CLAUDE_SDK_TEST_FOO", "PATH"]);
+}
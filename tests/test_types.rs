@@ -1,7 +1,7 @@
 //! Tests for Claude SDK types.
 
 use claude_code_sdk::{
-    AssistantMessage, ContentBlock, Message, PermissionMode, TextBlock,
+    AssistantMessage, ContentBlock, Message, PermissionMode, ResultSubtype, TextBlock,
     ToolResultContent, ToolUseBlock, UserMessage,
 };
 use std::collections::HashMap;
@@ -142,4 +142,97 @@ fn test_message_deserialization() {
         }
         _ => panic!("Expected assistant message"),
     }
-} 
\ No newline at end of file
+} 
+
+#[test]
+fn test_user_message_to_wire() {
+    let message = Message::User(UserMessage {
+        content: "Hello".to_string(),
+    });
+
+    assert_eq!(
+        message.to_wire(),
+        serde_json::json!({
+            "type": "user",
+            "message": {"role": "user", "content": "Hello"}
+        })
+    );
+}
+
+#[test]
+fn test_wire_round_trip() {
+    let golden = [
+        serde_json::json!({
+            "type": "user",
+            "message": {"role": "user", "content": "List files"}
+        }),
+        serde_json::json!({
+            "type": "assistant",
+            "message": {
+                "role": "assistant",
+                "content": [
+                    {"type": "text", "text": "Listing"},
+                    {"type": "tool_use", "id": "t1", "name": "Bash", "input": {"command": "ls"}},
+                    {"type": "tool_result", "tool_use_id": "t1", "content": "a.txt", "is_error": false},
+                    {"type": "tool_result", "tool_use_id": "t2", "content": [{"type": "text", "text": "b"}]}
+                ]
+            }
+        }),
+        serde_json::json!({
+            "type": "system",
+            "subtype": "init",
+            "session_id": "s1",
+            "tools": ["Bash"]
+        }),
+        serde_json::json!({
+            "type": "result",
+            "subtype": "success",
+            "duration_ms": 100,
+            "duration_api_ms": 80,
+            "is_error": false,
+            "num_turns": 1,
+            "session_id": "s1",
+            "total_cost_usd": 0.01,
+            "usage": {"input_tokens": 5},
+            "result": "a.txt"
+        }),
+    ];
+
+    for wire in golden {
+        let message = Message::from_wire(&wire).expect("Should parse wire message");
+        assert_eq!(message.to_wire(), wire);
+
+        let reparsed = Message::from_wire(&message.to_wire()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&reparsed).unwrap(),
+            serde_json::to_value(&message).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_from_wire_unknown_type() {
+    assert!(Message::from_wire(&serde_json::json!({"type": "stream_event"})).is_none());
+    assert!(Message::from_wire(&serde_json::json!("not an object")).is_none());
+}
+
+#[test]
+fn test_result_subtype_parsing() {
+    assert_eq!(ResultSubtype::from("success"), ResultSubtype::Success);
+    assert_eq!(ResultSubtype::from("error_max_turns"), ResultSubtype::ErrorMaxTurns);
+    assert_eq!(ResultSubtype::from("error_during_execution"), ResultSubtype::ErrorDuringExecution);
+    assert_eq!(ResultSubtype::from("error_max_budget_usd"), ResultSubtype::ErrorMaxBudgetUsd);
+
+    let unknown = ResultSubtype::from("error_something_new");
+    assert_eq!(unknown, ResultSubtype::Unknown("error_something_new".to_string()));
+    assert_eq!(unknown.as_str(), "error_something_new");
+    assert_eq!(ResultSubtype::ErrorMaxTurns.to_string(), "error_max_turns");
+}
+
+#[test]
+fn test_result_subtype_serde() {
+    assert_eq!(serde_json::to_value(ResultSubtype::ErrorMaxBudgetUsd).unwrap(), "error_max_budget_usd");
+    let unknown: ResultSubtype = serde_json::from_value(serde_json::json!("error_something_new")).unwrap();
+    assert_eq!(unknown, ResultSubtype::Unknown("error_something_new".to_string()));
+    assert_eq!(serde_json::to_value(&unknown).unwrap(), "error_something_new");
+}
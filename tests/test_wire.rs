@@ -0,0 +1,94 @@
+//! Tests for the borrowed wire-model message parser.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use claude_code_sdk::wire::{WireContent, WireMessage};
+use claude_code_sdk::{parse_message, parse_message_line, ContentBlock, Message, ToolResultContent};
+
+/// Parse a line through the map-based path for comparison
+fn parse_via_map(line: &str) -> Option<Message> {
+    let data: HashMap<String, serde_json::Value> = serde_json::from_str(line).unwrap();
+    parse_message(data)
+}
+
+fn assert_same_as_map_path(line: &str) {
+    let via_map = parse_via_map(line);
+    let via_wire = parse_message_line(line).expect("Should decode");
+    assert_eq!(
+        serde_json::to_value(&via_map).unwrap(),
+        serde_json::to_value(&via_wire).unwrap(),
+        "Wire and map paths disagree on {}",
+        line
+    );
+}
+
+#[test]
+fn test_wire_matches_map_path() {
+    let lines = [
+        r#"{"type": "user", "message": {"role": "user", "content": "Hello \"there\""}}"#,
+        r#"{"type": "assistant", "message": {"content": [{"type": "text", "text": "Hi"}, {"type": "thinking", "thinking": "hmm"}, {"type": "tool_use", "id": "t1", "name": "Read", "input": {"file_path": "/tmp/a", "limit": 10}}]}}"#,
+        r#"{"type": "assistant", "message": {"content": [{"type": "tool_result", "tool_use_id": "t1", "content": "ok", "is_error": false}, {"type": "tool_result", "tool_use_id": "t2", "content": [{"type": "text", "text": "x"}]}]}}"#,
+        r#"{"type": "system", "subtype": "init", "session_id": "s1", "tools": ["Read"]}"#,
+        r#"{"type": "result", "subtype": "success", "duration_ms": 10, "duration_api_ms": 5, "is_error": false, "num_turns": 2, "session_id": "s1", "total_cost_usd": 0.5, "usage": {"input_tokens": 3}, "result": "done"}"#,
+        r#"{"type": "result", "subtype": "success", "duration_ms": 10}"#,
+        r#"{"type": "assistant", "message": {"content": [{"type": "text"}]}}"#,
+        r#"{"type": "user", "message": {"content": [{"type": "tool_result", "tool_use_id": "t1"}]}}"#,
+        r#"{"type": "stream_event", "event": {}}"#,
+        r#"{"no_type": true}"#,
+        // Shared envelope fields with shapes other message types don't expect
+        r#"{"type": "system", "subtype": "compact_boundary", "message": "Compacting", "duration_ms": 1.5, "num_turns": "many", "is_error": "no", "total_cost_usd": "free", "session_id": 7}"#,
+        r#"{"type": "user", "message": "not an object"}"#,
+        r#"{"type": "result", "subtype": "success", "duration_ms": 10.5, "duration_api_ms": 5, "is_error": false, "num_turns": 2, "session_id": "s1"}"#,
+        r#"{"type": "result", "subtype": "success", "duration_ms": 10, "duration_api_ms": 5, "is_error": false, "num_turns": 2, "session_id": "s1", "total_cost_usd": "n/a", "model": 4}"#,
+    ];
+
+    for line in lines {
+        assert_same_as_map_path(line);
+    }
+}
+
+#[test]
+fn test_wire_borrows_unescaped_strings() {
+    let line = r#"{"type": "user", "message": {"content": "plain text"}}"#;
+    let wire = WireMessage::from_line(line).unwrap();
+
+    assert!(matches!(wire.message_type, Some(Cow::Borrowed("user"))));
+    match wire.body().unwrap().content.unwrap() {
+        WireContent::Text(Cow::Borrowed(text)) => assert_eq!(text, "plain text"),
+        other => panic!("Expected borrowed text, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_wire_keeps_tool_input_raw() {
+    let line = r#"{"type": "assistant", "message": {"content": [{"type": "tool_use", "id": "t1", "name": "Bash", "input": {"command": "ls -la"}}]}}"#;
+    let wire = WireMessage::from_line(line).unwrap();
+
+    let blocks = match wire.body().unwrap().content.unwrap() {
+        WireContent::Blocks(blocks) => blocks,
+        other => panic!("Expected blocks, got {:?}", other),
+    };
+    assert_eq!(blocks[0].input.unwrap().get(), r#"{"command": "ls -la"}"#);
+}
+
+#[test]
+fn test_parse_message_line_converts_blocks() {
+    let line = r#"{"type": "assistant", "message": {"content": [{"type": "tool_result", "tool_use_id": "t1", "content": "file contents"}]}}"#;
+
+    match parse_message_line(line).unwrap() {
+        Some(Message::Assistant(msg)) => match &msg.content[0] {
+            ContentBlock::ToolResult(block) => {
+                assert_eq!(block.tool_use_id, "t1");
+                assert!(matches!(&block.content, Some(ToolResultContent::Text(text)) if text == "file contents"));
+            }
+            other => panic!("Expected tool result, got {:?}", other),
+        },
+        other => panic!("Expected assistant message, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_message_line_invalid_json() {
+    assert!(parse_message_line(r#"{"type": "user""#).is_err());
+}